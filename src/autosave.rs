@@ -0,0 +1,150 @@
+//! Crash recovery for in-progress dictation
+//!
+//! The main loop periodically writes the current transcription to a session
+//! file of its own in the state directory (see `config::state_dir`), locked
+//! for as long as the run lasts. The file is removed on normal exit, so an
+//! unlocked one found at startup was left by a run that ended abnormally and
+//! can be resumed with `--resume`. Other instances running at the same time
+//! keep their files locked and aren't mistaken for crashed ones.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::process;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+use crate::config;
+
+const AUTOSAVE_INTERVAL_MS: u64 = 2000;
+
+/// Session files are named `session-<pid>-<start ms>.txt`
+const SESSION_PREFIX: &str = "session-";
+const SESSION_SUFFIX: &str = ".txt";
+
+pub struct Autosave {
+    session: Option<(PathBuf, File)>, // None when the file couldn't be created
+    saved_text: String,
+    last_write: Option<Instant>,
+}
+
+impl Autosave {
+    /// Create this run's session file. Autosave is off (logged) if it can't
+    /// be created.
+    pub fn new() -> Self {
+        let session = Self::create().map_err(|e| log::warn!("Autosave disabled: {}", e));
+        Self {
+            session: session.ok(),
+            saved_text: String::new(),
+            last_write: None,
+        }
+    }
+
+    /// Create a new session file only the user can read, without following
+    /// a symlink in its place, and lock it
+    fn create() -> anyhow::Result<(PathBuf, File)> {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let name = format!(
+            "{}{}-{}{}",
+            SESSION_PREFIX,
+            process::id(),
+            started,
+            SESSION_SUFFIX
+        );
+        let path = config::state_dir()?.join(name);
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let file = options.open(&path)?;
+        file.try_lock()?;
+        Ok((path, file))
+    }
+
+    /// The newest session file left behind by a run that ended abnormally,
+    /// with its text
+    pub fn find_abandoned() -> Option<(PathBuf, String)> {
+        let dir = config::state_dir().ok()?;
+        let mut found: Option<(SystemTime, PathBuf, String)> = None;
+        for entry in fs::read_dir(dir).ok()?.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            // Not following symlinks
+            let is_file = entry.file_type().is_ok_and(|t| t.is_file());
+            if !is_file || !name.starts_with(SESSION_PREFIX) || !name.ends_with(SESSION_SUFFIX) {
+                continue;
+            }
+
+            // Still locked by the run that's writing it
+            let Ok(file) = File::open(entry.path()) else {
+                continue;
+            };
+            if file.try_lock().is_err() {
+                continue;
+            }
+            let Ok(modified) = entry.metadata().and_then(|m| m.modified()) else {
+                continue;
+            };
+            if found
+                .as_ref()
+                .is_some_and(|(newest, ..)| *newest >= modified)
+            {
+                continue;
+            }
+            // Through the locked handle; Windows locks keep other handles out
+            let text = io::read_to_string(&file).unwrap_or_default();
+            if text.is_empty() {
+                // Nothing to recover
+                let _ = fs::remove_file(entry.path());
+                continue;
+            }
+            found = Some((modified, entry.path(), text));
+        }
+        found.map(|(_, path, text)| (path, text))
+    }
+
+    /// Save the text if it changed and the autosave interval has elapsed
+    pub fn tick(&mut self, text: &str) {
+        if text == self.saved_text {
+            return;
+        }
+        if let Some(last) = self.last_write {
+            if last.elapsed() < Duration::from_millis(AUTOSAVE_INTERVAL_MS) {
+                return;
+            }
+        }
+
+        // Best effort: a failed autosave shouldn't interrupt dictation
+        if let Some((_, file)) = &mut self.session {
+            let _ = Self::write(file, text);
+        }
+        self.saved_text = text.to_string();
+        self.last_write = Some(Instant::now());
+    }
+
+    fn write(mut file: &File, text: &str) -> io::Result<()> {
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(text.as_bytes())
+    }
+
+    /// Remove the session file (normal exit)
+    pub fn discard(&mut self) {
+        if let Some((path, file)) = self.session.take() {
+            drop(file);
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,126 @@
+//! Automatic stop conditions for a recording session
+//!
+//! - `--silence-timeout`: stop when the transcription hasn't changed for a while
+//! - `--max-duration`: stop after a fixed amount of time
+//! - `--max-chars` (with `--max-chars-policy stop`): stop once the
+//!   transcription is that long; main checks it against the whole text
+//!
+//! For `claudio once`, silence only counts once something has been said, so
+//! the session ends at the end of the first utterance.
+
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Which condition ended the session
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StopReason {
+    /// `--silence-timeout`
+    Silence,
+    /// `--max-duration`
+    MaxDuration,
+    /// `--max-chars`
+    MaxChars,
+}
+
+/// What happens once the transcription reaches `--max-chars`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LimitPolicy {
+    /// Finish the session
+    #[default]
+    Stop,
+    /// Keep listening, but drop text past the limit
+    Truncate,
+}
+
+impl FromStr for LimitPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "stop" => Ok(LimitPolicy::Stop),
+            "truncate" => Ok(LimitPolicy::Truncate),
+            _ => Err(()),
+        }
+    }
+}
+
+pub struct AutoStop {
+    silence_timeout: Option<Duration>,
+    max_duration: Option<Duration>,
+    start: Instant,
+    last_text: String,
+    last_change: Instant,
+    after_speech: bool, // Don't count silence before the first words
+}
+
+impl AutoStop {
+    pub fn new(silence_timeout: Option<Duration>, max_duration: Option<Duration>) -> Self {
+        let now = Instant::now();
+        Self {
+            silence_timeout,
+            max_duration,
+            start: now,
+            last_text: String::new(),
+            last_change: now,
+            after_speech: false,
+        }
+    }
+
+    /// Only start measuring silence once the transcription is non-empty
+    pub fn after_speech(mut self) -> Self {
+        self.after_speech = true;
+        self
+    }
+
+    /// Restart timing (e.g. after Ctrl+D or editing)
+    pub fn reset(&mut self) {
+        let now = Instant::now();
+        self.start = now;
+        self.last_change = now;
+    }
+
+    /// Time left until the nearest stop condition is met, as of the last
+    /// `should_stop`; `None` without one (or before speech for `claudio once`)
+    pub fn remaining(&self) -> Option<Duration> {
+        let now = Instant::now();
+        let max = self
+            .max_duration
+            .map(|max| max.saturating_sub(now.duration_since(self.start)));
+        let silence = self
+            .silence_timeout
+            .filter(|_| !(self.after_speech && self.last_text.is_empty()))
+            .map(|timeout| timeout.saturating_sub(now.duration_since(self.last_change)));
+        match (max, silence) {
+            (Some(max), Some(silence)) => Some(max.min(silence)),
+            (max, silence) => max.or(silence),
+        }
+    }
+
+    /// Feed the current transcription; returns the reason once a stop
+    /// condition is met.
+    /// Silence is only measured once `is_ready` is true, so mic warmup doesn't
+    /// count towards it.
+    pub fn should_stop(&mut self, text: &str, is_ready: bool) -> Option<StopReason> {
+        let now = Instant::now();
+
+        if text != self.last_text || !is_ready || (self.after_speech && text.is_empty()) {
+            self.last_text.clear();
+            self.last_text.push_str(text);
+            self.last_change = now;
+        }
+
+        if let Some(max) = self.max_duration {
+            if now.duration_since(self.start) >= max {
+                return Some(StopReason::MaxDuration);
+            }
+        }
+
+        if let Some(timeout) = self.silence_timeout {
+            if now.duration_since(self.last_change) >= timeout {
+                return Some(StopReason::Silence);
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,371 @@
+//! Command-line argument parsing
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use claudio::RecognizerOptions;
+
+use crate::autostop::LimitPolicy;
+use crate::output::LineEnding;
+use crate::ui::{Animation, Background};
+
+/// Project vocabulary file looked up by `--vocab-from`
+const VOCAB_FILE: &str = ".claudio-vocab";
+
+/// Subcommand given as the first argument
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Subcommand {
+    /// `claudio locales`: list the languages the engine supports
+    Locales,
+    /// `claudio doctor`: check that the engine can run
+    Doctor,
+    /// `claudio once`: record a single utterance headless, print it and exit
+    Once,
+}
+
+/// Parsed command-line options
+#[derive(Default)]
+pub struct Args {
+    /// Subcommand to run instead of recording
+    pub subcommand: Option<Subcommand>,
+    /// Print the version and build information, then exit
+    pub version: bool,
+    /// Print machine-readable JSON (`locales`, `doctor` and `--version` only)
+    pub json: bool,
+    /// Options forwarded to the speech recognizer
+    pub recognizer: RecognizerOptions,
+    /// Ring the terminal bell once recognition is ready
+    pub beep: bool,
+    /// Fade-in timing for new text
+    pub animation: Animation,
+    /// Use ASCII symbols instead of Unicode ones
+    pub ascii: bool,
+    /// Wrap the live display at the terminal width, splitting words
+    pub no_word_wrap: bool,
+    /// Show a steady cursor while editing instead of a blinking one
+    pub no_cursor_blink: bool,
+    /// How long a rewritten transcription must hold before it's displayed
+    pub debounce: Duration,
+    /// Language of the UI strings (config or system locale when not given)
+    pub ui_lang: Option<String>,
+    /// Terminal background (detected when not given)
+    pub background: Option<Background>,
+    /// Recover the transcription autosaved by a previous run
+    pub resume: bool,
+    /// Run without the terminal UI and print the result when done
+    pub no_ui: bool,
+    /// Only pass audio to the recognizer while the talk key is held
+    pub push_to_talk: bool,
+    /// Wait for a key press before starting to record
+    pub start_paused: bool,
+    /// Show the engine, language and where recognition runs
+    pub show_status: bool,
+    /// Listen again when the recognizer ends a session, until quit
+    pub continuous: bool,
+    /// Hide the controls bar
+    pub minimal: bool,
+    /// Don't show "Speak now..." before anything is recognized
+    pub no_placeholder: bool,
+    /// Finish after the transcription hasn't changed for this long
+    pub silence_timeout: Option<Duration>,
+    /// Finish after recording for this long
+    pub max_duration: Option<Duration>,
+    /// Longest transcription output, in characters
+    pub max_chars: Option<usize>,
+    /// Whether reaching `max_chars` finishes or only truncates
+    pub max_chars_policy: LimitPolicy,
+    /// Exit status when --silence-timeout, --max-duration or --max-chars
+    /// ended recording, in place of each one's own
+    pub timeout_exit_code: Option<i32>,
+    /// Command to exec with the transcription as stdin (everything after `--`)
+    pub exec_command: Option<Vec<String>>,
+    /// Substitute the transcription for `{}` in the command's arguments
+    pub arg_template: bool,
+    /// Spawn the command at startup and stream every update to its stdin
+    pub stream_exec: bool,
+    /// Print each finished phrase as it comes (headless)
+    pub stream: bool,
+    /// Print the transcription and the command instead of running it
+    pub dry_run: bool,
+    /// Ask before running the command
+    pub confirm: bool,
+    /// Write the final transcription to this file instead of stdout
+    pub output: Option<PathBuf>,
+    /// Copy the final transcription to the clipboard instead of stdout
+    pub clipboard: bool,
+    /// Also add the final transcription to the end of the clipboard
+    pub clipboard_append: bool,
+    /// POST the final transcription as JSON to this URL
+    pub webhook: Option<String>,
+    /// Still print the transcription when cancelled with Ctrl+C
+    pub keep_on_cancel: bool,
+    /// Output the final transcription as-is instead of normalizing whitespace
+    pub no_trim: bool,
+    /// WAV file to save the captured audio to
+    pub save_audio: Option<PathBuf>,
+    /// Keep control characters in the output instead of stripping them
+    pub raw: bool,
+    /// Don't end the printed transcription with a newline
+    pub no_newline: bool,
+    /// Line break used in the output
+    pub line_ending: LineEnding,
+    /// File of words to mask in the output
+    pub filter_words: Option<PathBuf>,
+    /// Also mask filtered words in the live display
+    pub filter_live: bool,
+    /// Text added before the final transcription
+    pub prepend: Option<String>,
+    /// Text added after the final transcription
+    pub append: Option<String>,
+    /// Enable debug logging
+    pub verbose: bool,
+    /// Write logs to this file
+    pub log_file: Option<PathBuf>,
+    /// Print latency measurements to stderr on exit
+    pub stats: bool,
+    /// Fail unless the output matches the mock script's expected text
+    pub self_check: bool,
+}
+
+impl Args {
+    /// Parse the process arguments
+    pub fn parse() -> Result<Self> {
+        Self::parse_from(env::args().skip(1))
+    }
+
+    /// Parse arguments (excluding the program name)
+    pub fn parse_from(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter().peekable();
+
+        parsed.subcommand = args
+            .next_if(|arg| arg == "locales" || arg == "doctor" || arg == "once")
+            .map(|arg| match arg.as_str() {
+                "locales" => Subcommand::Locales,
+                "doctor" => Subcommand::Doctor,
+                _ => Subcommand::Once,
+            });
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => {
+                    let rest: Vec<String> = args.by_ref().collect();
+                    if !rest.is_empty() {
+                        parsed.exec_command = Some(rest);
+                    }
+                }
+                "--autopunct" => parsed.recognizer.autopunct = true,
+                "--no-partial" => parsed.recognizer.final_only = true,
+                "--beep" => parsed.beep = true,
+                "--ascii" => parsed.ascii = true,
+                "--no-word-wrap" => parsed.no_word_wrap = true,
+                "--no-cursor-blink" => parsed.no_cursor_blink = true,
+                "--json" => parsed.json = true,
+                "--version" | "-V" => parsed.version = true,
+                "--resume" => parsed.resume = true,
+                "--arg-template" => parsed.arg_template = true,
+                "--stream-exec" => parsed.stream_exec = true,
+                "--stream" => parsed.stream = true,
+                "--dry-run" => parsed.dry_run = true,
+                "--confirm" => parsed.confirm = true,
+                "--clipboard" => parsed.clipboard = true,
+                "--clipboard-append" => parsed.clipboard_append = true,
+                "--keep-on-cancel" => parsed.keep_on_cancel = true,
+                "--no-ui" | "--quiet" => parsed.no_ui = true,
+                "--push-to-talk" => parsed.push_to_talk = true,
+                "--start-paused" => parsed.start_paused = true,
+                "--show-status" => parsed.show_status = true,
+                "--minimal" => parsed.minimal = true,
+                "--continuous" => parsed.continuous = true,
+                "--no-placeholder" => parsed.no_placeholder = true,
+                "--trim" => parsed.no_trim = false,
+                "--no-trim" => parsed.no_trim = true,
+                "--raw" => parsed.raw = true,
+                "--newline" => parsed.no_newline = false,
+                "--no-newline" => parsed.no_newline = true,
+                "--verbose" | "-v" => parsed.verbose = true,
+                "--stats" => parsed.stats = true,
+                "--self-check" => parsed.self_check = true,
+                "--save-audio" => {
+                    parsed.save_audio = Some(parse_value(&arg, args.next())?);
+                }
+                "--output" => {
+                    parsed.output = Some(parse_value(&arg, args.next())?);
+                }
+                "--webhook" => {
+                    parsed.webhook = Some(parse_value(&arg, args.next())?);
+                }
+                "--line-ending" => {
+                    parsed.line_ending = parse_value(&arg, args.next())?;
+                }
+                "--ui-lang" => {
+                    parsed.ui_lang = Some(parse_value(&arg, args.next())?);
+                }
+                "--background" => {
+                    parsed.background = Some(parse_value(&arg, args.next())?);
+                }
+                "--filter-words" => {
+                    parsed.filter_words = Some(parse_value(&arg, args.next())?);
+                }
+                "--filter-live" => parsed.filter_live = true,
+                "--prepend" => parsed.prepend = Some(parse_value(&arg, args.next())?),
+                "--append" => parsed.append = Some(parse_value(&arg, args.next())?),
+                "--prepend-file" => parsed.prepend = Some(read_file(&arg, args.next())?),
+                "--append-file" => parsed.append = Some(read_file(&arg, args.next())?),
+                "--log-file" => {
+                    parsed.log_file = Some(parse_value(&arg, args.next())?);
+                }
+                "--silence-timeout" => {
+                    parsed.silence_timeout = Some(parse_secs(&arg, args.next())?);
+                }
+                "--max-duration" => {
+                    parsed.max_duration = Some(parse_secs(&arg, args.next())?);
+                }
+                "--max-chars" => {
+                    parsed.max_chars = Some(parse_value(&arg, args.next())?);
+                }
+                "--max-chars-policy" => {
+                    parsed.max_chars_policy = parse_value(&arg, args.next())?;
+                }
+                "--timeout-exit-code" => {
+                    parsed.timeout_exit_code = Some(parse_value(&arg, args.next())?);
+                }
+                "--engine" => {
+                    parsed.recognizer.engine = parse_value(&arg, args.next())?;
+                }
+                "--lang" => {
+                    let tags: String = parse_value(&arg, args.next())?;
+                    parsed.recognizer.languages = tags
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(str::to_string)
+                        .collect();
+                }
+                "--char-delay-ms" => {
+                    parsed.animation.char_delay_ms = parse_value::<u64>(&arg, args.next())? as f32;
+                }
+                "--fade-ms" => {
+                    parsed.animation.fade_ms = parse_value::<u64>(&arg, args.next())? as f32;
+                }
+                "--gain" => {
+                    parsed.recognizer.gain = parse_value(&arg, args.next())?;
+                }
+                "--debounce-ms" => {
+                    parsed.debounce = Duration::from_millis(parse_value(&arg, args.next())?);
+                }
+                "--endpoint-silence-ms" => {
+                    let ms = parse_value(&arg, args.next())?;
+                    parsed.recognizer.endpoint_silence = Some(Duration::from_millis(ms));
+                }
+                "--task" => {
+                    parsed.recognizer.task_hint = parse_value(&arg, args.next())?;
+                }
+                "--grammar" => {
+                    parsed.recognizer.grammar = read_phrases(&arg, args.next())?;
+                }
+                "--vocab-from" => {
+                    parsed.recognizer.vocabulary = read_vocab(&arg, args.next())?;
+                }
+                "--warmup-ms" => {
+                    parsed.recognizer.warmup_ms = parse_value(&arg, args.next())?;
+                }
+                _ => return Err(anyhow!("Unknown option: {}", arg)),
+            }
+        }
+
+        let json_subcommand = matches!(
+            parsed.subcommand,
+            Some(Subcommand::Locales | Subcommand::Doctor)
+        );
+        if parsed.json && !parsed.version && !json_subcommand {
+            return Err(anyhow!(
+                "--json is only supported by `claudio locales`, `claudio doctor` and --version"
+            ));
+        }
+        if parsed.continuous && parsed.subcommand == Some(Subcommand::Once) {
+            return Err(anyhow!(
+                "--continuous can't be used with `claudio once` (it ends after one phrase)"
+            ));
+        }
+        if parsed.stream && parsed.exec_command.is_some() {
+            return Err(anyhow!(
+                "--stream prints to stdout; use --stream-exec to stream to a command"
+            ));
+        }
+        if parsed.max_chars == Some(0) {
+            return Err(anyhow!("--max-chars must be at least 1"));
+        }
+        if parsed.confirm && parsed.stream_exec {
+            return Err(anyhow!(
+                "--confirm can't be used with --stream-exec (the command starts right away)"
+            ));
+        }
+
+        Ok(parsed)
+    }
+}
+
+/// Parse the value following an option
+fn parse_value<T: FromStr>(name: &str, value: Option<String>) -> Result<T> {
+    let value = value.ok_or_else(|| anyhow!("Missing value for {}", name))?;
+    value
+        .parse()
+        .map_err(|_| anyhow!("Invalid value for {}: {}", name, value))
+}
+
+/// Read the contents of the file named by an option's value
+fn read_file(name: &str, value: Option<String>) -> Result<String> {
+    let path: PathBuf = parse_value(name, value)?;
+    fs::read_to_string(&path)
+        .map_err(|e| anyhow!("Failed to read {} for {}: {}", path.display(), name, e))
+}
+
+/// Read the terms of the vocabulary file in the directory named by an
+/// option's value or the nearest one above it; none if there isn't one
+fn read_vocab(name: &str, value: Option<String>) -> Result<Vec<String>> {
+    let dir: PathBuf = parse_value(name, value)?;
+    let dir = dir
+        .canonicalize()
+        .map_err(|e| anyhow!("Invalid value for {}: {}: {}", name, dir.display(), e))?;
+    let Some(path) = dir
+        .ancestors()
+        .map(|dir| dir.join(VOCAB_FILE))
+        .find(|path| path.is_file())
+    else {
+        return Ok(Vec::new());
+    };
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| anyhow!("Failed to read {} for {}: {}", path.display(), name, e))?;
+    Ok(phrases(&contents))
+}
+
+/// Read the phrases listed in the file named by an option's value, failing
+/// if there are none
+fn read_phrases(name: &str, value: Option<String>) -> Result<Vec<String>> {
+    let phrases = phrases(&read_file(name, value)?);
+    if phrases.is_empty() {
+        return Err(anyhow!("The file for {} lists no phrases", name));
+    }
+    Ok(phrases)
+}
+
+/// One phrase per line, skipping blank lines and `#` comments
+fn phrases(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parse a duration given in (possibly fractional) seconds
+fn parse_secs(name: &str, value: Option<String>) -> Result<Duration> {
+    let secs: f64 = parse_value(name, value)?;
+    Duration::try_from_secs_f64(secs).map_err(|_| anyhow!("Invalid value for {}: {}", name, secs))
+}
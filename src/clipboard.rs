@@ -0,0 +1,143 @@
+//! Copying the transcription to the clipboard (`--clipboard`) or appending
+//! it (`--clipboard-append`)
+//!
+//! There's no portable clipboard history API, so appending reads the current
+//! clipboard text, adds the transcription on a new line, and writes the
+//! result back. This uses the platform's clipboard tools: pbpaste/pbcopy on macOS,
+//! PowerShell on Windows, and wl-clipboard (Wayland), xclip or xsel (X11)
+//! elsewhere.
+
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Result};
+
+/// Commands that read and write the clipboard text
+struct Tool {
+    paste: &'static [&'static str],
+    copy: &'static [&'static str],
+}
+
+#[cfg(target_os = "macos")]
+const TOOLS: &[Tool] = &[Tool {
+    paste: &["pbpaste"],
+    copy: &["pbcopy"],
+}];
+
+#[cfg(target_os = "windows")]
+const TOOLS: &[Tool] = &[Tool {
+    paste: &["powershell", "-NoProfile", "-Command", "Get-Clipboard -Raw"],
+    copy: &[
+        "powershell",
+        "-NoProfile",
+        "-Command",
+        "[Console]::In.ReadToEnd() | Set-Clipboard",
+    ],
+}];
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const TOOLS: &[Tool] = &[
+    Tool {
+        paste: &["wl-paste", "--no-newline"],
+        copy: &["wl-copy"],
+    },
+    Tool {
+        paste: &["xclip", "-selection", "clipboard", "-o"],
+        copy: &["xclip", "-selection", "clipboard", "-i"],
+    },
+    Tool {
+        paste: &["xsel", "--clipboard", "--output"],
+        copy: &["xsel", "--clipboard", "--input"],
+    },
+];
+
+/// Replace the clipboard text with `text`
+pub fn set(text: &str) -> Result<()> {
+    for tool in usable_tools() {
+        // Reading tells whether the tool is installed
+        if paste(tool.paste)?.is_none() {
+            continue;
+        }
+        log::debug!("Copying to the clipboard with {}", tool.copy[0]);
+        return copy(tool.copy, text);
+    }
+    Err(no_tool("--clipboard"))
+}
+
+/// Add `text` to the end of the clipboard, on a line of its own
+pub fn append(text: &str) -> Result<()> {
+    for tool in usable_tools() {
+        let Some(current) = paste(tool.paste)? else {
+            continue; // Not installed
+        };
+
+        let combined = if current.is_empty() || current.ends_with('\n') {
+            format!("{}{}", current, text)
+        } else {
+            format!("{}\n{}", current, text)
+        };
+        log::debug!("Appending to the clipboard with {}", tool.copy[0]);
+        return copy(tool.copy, &combined);
+    }
+    Err(no_tool("--clipboard-append"))
+}
+
+/// The tools to try, in order. Wayland tools are installed on some X11
+/// desktops and vice versa, so the ones that can't reach a display are left
+/// out.
+fn usable_tools() -> impl Iterator<Item = &'static Tool> {
+    TOOLS
+        .iter()
+        .filter(|tool| tool.paste[0] != "wl-paste" || std::env::var_os("WAYLAND_DISPLAY").is_some())
+}
+
+fn no_tool(option: &str) -> anyhow::Error {
+    anyhow!(
+        "No clipboard tool found for {} (tried {})",
+        option,
+        TOOLS
+            .iter()
+            .map(|tool| tool.paste[0])
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// Read the clipboard text, or `None` if the tool isn't installed. An empty
+/// clipboard makes some tools fail, so a failure reads as empty.
+fn paste(command: &[&str]) -> Result<Option<String>> {
+    let output = match Command::new(command[0])
+        .args(&command[1..])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(anyhow!("Failed to run {}: {}", command[0], e)),
+    };
+    if !output.status.success() {
+        return Ok(Some(String::new()));
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+/// Replace the clipboard text
+fn copy(command: &[&str], text: &str) -> Result<()> {
+    let mut child = Command::new(command[0])
+        .args(&command[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run {}: {}", command[0], e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|e| anyhow!("Failed to write to {}: {}", command[0], e))?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow!("{} failed to set the clipboard", command[0]));
+    }
+    Ok(())
+}
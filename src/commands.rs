@@ -0,0 +1,109 @@
+//! Spoken formatting commands
+//!
+//! Phrases like "new line" are replaced in the transcription before it is
+//! displayed or output. Matching is case-insensitive on whole words and
+//! ignores punctuation the recognizer attached to them. Extra phrases can be
+//! added in the `[commands]` table of the config file:
+//!
+//! ```toml
+//! [commands]
+//! "bullet point" = "\n- "
+//! "um" = ""
+//! ```
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Built-in phrases, overridable from the config file
+const DEFAULT_COMMANDS: &[(&str, &str)] = &[("new line", "\n"), ("new paragraph", "\n\n")];
+
+pub struct VoiceCommands {
+    // (lowercased phrase words, replacement), longest phrase first
+    commands: Vec<(Vec<String>, String)>,
+}
+
+impl VoiceCommands {
+    /// Build the phrase map from config overrides on top of the defaults
+    pub fn from_config(commands: &HashMap<String, String>) -> Self {
+        let mut merged: HashMap<String, String> = DEFAULT_COMMANDS
+            .iter()
+            .map(|(phrase, replacement)| (phrase.to_string(), replacement.to_string()))
+            .collect();
+        for (phrase, replacement) in commands {
+            merged.insert(phrase.to_lowercase(), replacement.clone());
+        }
+
+        let mut commands: Vec<(Vec<String>, String)> = merged
+            .into_iter()
+            .map(|(phrase, replacement)| {
+                let words: Vec<String> = phrase.split_whitespace().map(str::to_string).collect();
+                (words, replacement)
+            })
+            .filter(|(words, _)| !words.is_empty())
+            .collect();
+        commands.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+
+        Self { commands }
+    }
+
+    /// Replace spoken commands in `text`. Text without any command is
+    /// returned unchanged.
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let keys: Vec<String> = words.iter().map(|w| match_key(w)).collect();
+
+        if !(0..keys.len()).any(|i| self.match_at(&keys[i..]).is_some()) {
+            return Cow::Borrowed(text);
+        }
+
+        let mut out = String::with_capacity(text.len());
+        let mut need_space = false;
+        let mut i = 0;
+        while i < words.len() {
+            match self.match_at(&keys[i..]) {
+                Some((len, replacement)) => {
+                    i += len;
+                    if replacement.is_empty() {
+                        continue;
+                    }
+                    // Whitespace in the replacement stands in for the word gap
+                    if need_space && !replacement.starts_with(char::is_whitespace) {
+                        out.push(' ');
+                    }
+                    out.push_str(replacement);
+                    need_space = !replacement.ends_with(char::is_whitespace);
+                }
+                None => {
+                    if need_space {
+                        out.push(' ');
+                    }
+                    out.push_str(words[i]);
+                    need_space = true;
+                    i += 1;
+                }
+            }
+        }
+
+        Cow::Owned(out)
+    }
+
+    /// Longest command whose phrase starts at `keys[0]`: (word count, replacement)
+    fn match_at(&self, keys: &[String]) -> Option<(usize, &str)> {
+        self.commands
+            .iter()
+            .find(|(phrase, _)| keys.len() >= phrase.len() && keys[..phrase.len()] == phrase[..])
+            .map(|(phrase, replacement)| (phrase.len(), replacement.as_str()))
+    }
+}
+
+impl Default for VoiceCommands {
+    fn default() -> Self {
+        Self::from_config(&HashMap::new())
+    }
+}
+
+/// A word as compared against phrases: lowercase, without surrounding punctuation
+pub fn match_key(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
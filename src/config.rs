@@ -0,0 +1,143 @@
+//! User configuration file
+//!
+//! Loaded from `$CLAUDIO_CONFIG` if set, otherwise:
+//! - Unix: `$XDG_CONFIG_HOME/claudio/config.toml` (default `~/.config/claudio/config.toml`)
+//! - Windows: `%APPDATA%\claudio\config.toml`
+//!
+//! A missing file is not an error; every key is optional.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+#[cfg(unix)]
+use std::os::unix::fs::DirBuilderExt;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+/// One or more key chords bound to an action
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyBinding {
+    pub fn chords(&self) -> Vec<&str> {
+        match self {
+            KeyBinding::One(chord) => vec![chord.as_str()],
+            KeyBinding::Many(chords) => chords.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Action name → key chord(s), e.g. `finish = "esc"`
+    pub keys: HashMap<String, KeyBinding>,
+    /// Spoken phrase → replacement text, e.g. `"new line" = "\n"`
+    pub commands: HashMap<String, String>,
+    /// Command to run when none is given after `--`, e.g. `["ollama", "run", "llama3"]`
+    pub default_exec: Option<Vec<String>>,
+    /// Language of the UI strings, e.g. `"de"` (the system locale if unset)
+    pub ui_lang: Option<String>,
+    /// Replacements for single UI strings, e.g. `speak_now = "Go ahead"`
+    pub messages: MessageOverrides,
+}
+
+/// UI strings that can be replaced from the config
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct MessageOverrides {
+    pub speak_now: Option<String>,
+    pub hold_to_talk: Option<String>,
+    pub press_to_start: Option<String>,
+    pub loading_model: Option<String>,
+    pub reconnecting: Option<String>,
+    pub no_audio: Option<String>,
+    pub device_changed: Option<String>,
+    pub dropping_audio: Option<String>,
+    pub limit_reached: Option<String>,
+    pub on_device: Option<String>,
+    pub server: Option<String>,
+    // Controls bar labels (recording)
+    pub submit: Option<String>,
+    pub edit: Option<String>,
+    pub discard: Option<String>,
+    pub clear: Option<String>,
+    pub pause: Option<String>,
+    pub cancel: Option<String>,
+}
+
+impl Config {
+    /// Load the config file, or defaults if it doesn't exist
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&contents).map_err(|e| anyhow!("Invalid config {}: {}", path.display(), e))
+    }
+
+    /// Location of the config file
+    pub fn path() -> Option<PathBuf> {
+        if let Ok(path) = env::var("CLAUDIO_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+
+        let base = if cfg!(target_os = "windows") {
+            env::var("APPDATA").ok().map(PathBuf::from)
+        } else {
+            env::var("XDG_CONFIG_HOME")
+                .ok()
+                .map(PathBuf::from)
+                .or_else(|| {
+                    env::var("HOME")
+                        .ok()
+                        .map(|h| PathBuf::from(h).join(".config"))
+                })
+        };
+        base.map(|dir| dir.join("claudio").join("config.toml"))
+    }
+}
+
+/// Per-user directory for files written while running (autosaved sessions,
+/// logs), created on first use:
+/// - Unix: `$XDG_STATE_HOME/claudio` (default `~/.local/state/claudio`), only
+///   accessible to the user
+/// - Windows: `%LOCALAPPDATA%\claudio`
+pub fn state_dir() -> Result<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        env::var("LOCALAPPDATA").ok().map(PathBuf::from)
+    } else {
+        env::var("XDG_STATE_HOME")
+            .ok()
+            .map(PathBuf::from)
+            .or_else(|| {
+                env::var("HOME")
+                    .ok()
+                    .map(|h| PathBuf::from(h).join(".local").join("state"))
+            })
+    };
+    let dir = base
+        .ok_or_else(|| anyhow!("No home directory to keep state in"))?
+        .join("claudio");
+
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    builder.mode(0o700);
+    builder
+        .create(&dir)
+        .map_err(|e| anyhow!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
@@ -0,0 +1,98 @@
+//! `--confirm`: ask before running the `--` command
+//!
+//! The transcription and the resolved command line are drawn inline, the
+//! same way as the recording UI, and a single key answers: `y` runs the
+//! command, anything that means "no" (`n`, Esc, Enter, Ctrl+C) doesn't.
+
+use anyhow::{anyhow, Result};
+use termwiz::caps::Capabilities;
+use termwiz::cell::{CellAttributes, Intensity};
+use termwiz::color::{AnsiColor, ColorAttribute};
+use termwiz::input::{InputEvent, KeyCode, Modifiers};
+use termwiz::terminal::{SystemTerminal, Terminal};
+
+use crate::inline_term::InlineTerminal;
+
+/// Rows of transcription shown above the prompt; longer text is cut short
+const MAX_TEXT_ROWS: usize = 6;
+
+/// Show the transcription and command, returns whether to run it
+pub fn ask(text: &str, command: &str) -> Result<bool> {
+    let caps = Capabilities::new_from_env().map_err(|e| anyhow!("{}", e))?;
+    let terminal = SystemTerminal::new(caps).map_err(|e| anyhow!("{}", e))?;
+    let mut term = InlineTerminal::new(terminal, 1)?;
+    term.terminal()
+        .set_raw_mode()
+        .map_err(|e| anyhow!("{}", e))?;
+
+    let (width, _) = term.surface().dimensions();
+    let mut rows = wrap(text, width);
+    if rows.len() > MAX_TEXT_ROWS {
+        rows.truncate(MAX_TEXT_ROWS);
+        let last = &mut rows[MAX_TEXT_ROWS - 1];
+        if last.chars().count() >= width {
+            last.pop();
+        }
+        last.push('…');
+    }
+    let text_rows = rows.len();
+    rows.push(format!("Run {}?", command));
+    rows.push("y run • n print instead".to_string());
+
+    term.resize_height(rows.len())?;
+    let surface = term.surface();
+    surface.clear();
+    let plain = CellAttributes::default();
+    let mut dim = CellAttributes::default();
+    dim.set_foreground(ColorAttribute::PaletteIndex(8));
+    let mut key = CellAttributes::default();
+    key.set_foreground(AnsiColor::Aqua)
+        .set_intensity(Intensity::Bold);
+    for (y, row) in rows.iter().enumerate() {
+        let attrs = if y < text_rows { &plain } else { &dim };
+        surface.set_text(0, y, row, attrs.clone());
+    }
+    // Highlight the keys in the hint line
+    let hint_row = rows.len() - 1;
+    surface.set_text(0, hint_row, "y", key.clone());
+    surface.set_text("y run • ".chars().count(), hint_row, "n", key);
+    term.render()?;
+
+    let answer = loop {
+        let event = term
+            .terminal()
+            .poll_input(None)
+            .map_err(|e| anyhow!("{}", e))?;
+        let Some(InputEvent::Key(event)) = event else {
+            continue;
+        };
+        match event.key {
+            KeyCode::Char('y') | KeyCode::Char('Y') => break true,
+            KeyCode::Char('c') if event.modifiers.contains(Modifiers::CTRL) => break false,
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Escape | KeyCode::Enter => {
+                break false
+            }
+            _ => {}
+        }
+    };
+
+    term.cleanup()?;
+    term.terminal()
+        .set_cooked_mode()
+        .map_err(|e| anyhow!("{}", e))?;
+    Ok(answer)
+}
+
+/// Split text into rows of at most `width` characters, keeping line breaks
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut rows = Vec::new();
+    for line in text.lines() {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.is_empty() {
+            rows.push(String::new());
+        }
+        rows.extend(chars.chunks(width).map(|chunk| chunk.iter().collect()));
+    }
+    rows
+}
@@ -0,0 +1,45 @@
+//! Smoothing jittery partial results for `--debounce-ms`
+//!
+//! Some recognizers (Vosk in particular) flip between hypotheses every few
+//! tens of milliseconds. Text that only adds to what's shown passes through
+//! right away; a rewrite is only shown once the recognizer has stuck with it
+//! for the debounce time.
+
+use std::time::{Duration, Instant};
+
+pub struct Debounce {
+    delay: Duration,
+    shown: String,
+    pending: Option<(String, Instant)>, // Rewrite waiting to settle, and since when
+}
+
+impl Debounce {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            shown: String::new(),
+            pending: None,
+        }
+    }
+
+    /// The text to show for the recognizer's latest `text`
+    pub fn update(&mut self, text: String) -> String {
+        // Appends, and the empty text a new session starts with, aren't held
+        let immediate = self.delay.is_zero() || text.is_empty() || text.starts_with(&self.shown);
+        match &self.pending {
+            _ if immediate => self.show(text),
+            Some((pending, since)) if *pending == text => {
+                if since.elapsed() >= self.delay {
+                    self.show(text);
+                }
+            }
+            _ => self.pending = Some((text, Instant::now())),
+        }
+        self.shown.clone()
+    }
+
+    fn show(&mut self, text: String) {
+        self.pending = None;
+        self.shown = text;
+    }
+}
@@ -0,0 +1,106 @@
+//! External editor for the transcription
+//!
+//! The editor is taken from `$CLAUDIO_EDITOR`, `$VISUAL` or `$EDITOR` (in
+//! that order) and may include arguments, e.g. `code --wait`. GUI editors
+//! that hand the file to a running instance and return straight away are
+//! given their wait flag automatically when it's missing.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+
+/// An editor returning faster than this without changes probably didn't wait
+const INSTANT_RETURN: Duration = Duration::from_secs(1);
+
+/// Open text in the external editor, returns the edited text
+pub fn open(text: &str) -> Result<String> {
+    // Create temporary file
+    let tmp_path = env::temp_dir().join(format!("claudio-{}.txt", std::process::id()));
+    fs::write(&tmp_path, text)?;
+
+    let command = editor_command();
+    log::debug!("Opening editor: {:?}", command);
+
+    let started = Instant::now();
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .arg(&tmp_path)
+        .status()
+        .map_err(|e| anyhow!("Failed to run editor {}: {}", command[0], e));
+    let status = match status {
+        Ok(status) => status,
+        Err(e) => {
+            fs::remove_file(&tmp_path)?;
+            return Err(e);
+        }
+    };
+
+    if !status.success() {
+        fs::remove_file(&tmp_path)?;
+        return Err(anyhow!("Editor exited with non-zero status"));
+    }
+
+    // Read edited content
+    let edited = fs::read_to_string(&tmp_path)?;
+
+    // Clean up
+    fs::remove_file(&tmp_path)?;
+
+    if edited == text && started.elapsed() < INSTANT_RETURN {
+        log::warn!(
+            "Editor {} returned immediately; if it's a GUI editor, set CLAUDIO_EDITOR \
+             with its wait flag (e.g. \"code --wait\")",
+            command[0]
+        );
+    }
+
+    Ok(edited.trim_end().to_string())
+}
+
+/// Program and arguments to run: $CLAUDIO_EDITOR > $VISUAL > $EDITOR >
+/// platform default
+fn editor_command() -> Vec<String> {
+    let editor = ["CLAUDIO_EDITOR", "VISUAL", "EDITOR"]
+        .iter()
+        .find_map(|var| env::var(var).ok().filter(|v| !v.trim().is_empty()))
+        .unwrap_or_else(|| {
+            if cfg!(target_os = "windows") {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        });
+
+    // A path to an existing program is kept whole, even if it has spaces
+    // (e.g. "C:\Program Files\...")
+    let mut command: Vec<String> = if Path::new(&editor).is_file() {
+        vec![editor]
+    } else {
+        editor.split_whitespace().map(String::from).collect()
+    };
+
+    if let Some(flag) = wait_flag(&command[0]) {
+        if !command[1..].iter().any(|arg| arg == flag) {
+            command.insert(1, flag.to_string());
+        }
+    }
+    command
+}
+
+/// Flag that makes a GUI editor block until the file is closed
+fn wait_flag(program: &str) -> Option<&'static str> {
+    let name = Path::new(program)
+        .file_stem()?
+        .to_str()?
+        .to_ascii_lowercase();
+    match name.as_str() {
+        "code" | "code-insiders" | "codium" | "cursor" | "subl" | "zed" => Some("--wait"),
+        "mate" => Some("-w"),
+        "gvim" | "mvim" => Some("-f"),
+        _ => None,
+    }
+}
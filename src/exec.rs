@@ -0,0 +1,156 @@
+//! Running the `--` command with the transcription
+//!
+//! By default the transcription is written to the command's stdin. With
+//! `--arg-template`, every `{}` in the command's arguments is replaced by the
+//! transcription instead and nothing is written to stdin; if the command has
+//! no `{}` placeholder, the transcription is piped to stdin as usual.
+//!
+//! Arguments are passed directly to the process (no shell is involved), so
+//! the substituted text never needs escaping.
+//!
+//! With `--stream-exec` the command is instead spawned once at startup and
+//! receives every transcription update on stdin, one per line.
+//!
+//! `--dry-run` prints the command line that would be run instead.
+
+use std::borrow::Cow;
+use std::io::Write;
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+use anyhow::{anyhow, Result};
+
+use crate::output;
+
+/// Placeholder replaced by the transcription in `--arg-template` mode
+pub const PLACEHOLDER: &str = "{}";
+
+/// Build the command line to run, substituting the transcription when
+/// `arg_template` is set. Returns the arguments and whether the transcription
+/// still needs to be written to stdin.
+pub fn resolve(cmd_args: &[String], text: &str, arg_template: bool) -> (Vec<String>, bool) {
+    if arg_template && cmd_args.iter().any(|a| a.contains(PLACEHOLDER)) {
+        let args = cmd_args
+            .iter()
+            .map(|a| a.replace(PLACEHOLDER, text))
+            .collect();
+        (args, false)
+    } else {
+        (cmd_args.to_vec(), true)
+    }
+}
+
+/// Describe what `run` would do, for `--dry-run`
+pub fn preview(cmd_args: &[String], text: &str, arg_template: bool) -> String {
+    let (args, pipe_stdin) = resolve(cmd_args, text, arg_template);
+    if pipe_stdin {
+        format!("{} (transcription on stdin)", command_line(&args))
+    } else {
+        command_line(&args)
+    }
+}
+
+/// The arguments as a command line, quoted for a POSIX shell
+pub fn command_line(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Single-quote an argument unless it only has shell-safe characters
+fn quote(arg: &str) -> Cow<'_, str> {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if safe {
+        Cow::Borrowed(arg)
+    } else {
+        Cow::Owned(format!("'{}'", arg.replace('\'', "'\\''")))
+    }
+}
+
+/// Run the command and return its exit code
+pub fn run(cmd_args: &[String], text: &str, arg_template: bool) -> Result<i32> {
+    let (args, pipe_stdin) = resolve(cmd_args, text, arg_template);
+    let program = args
+        .first()
+        .ok_or_else(|| anyhow!("No command given after --"))?;
+
+    let mut child = Command::new(program)
+        .args(&args[1..])
+        .stdin(if pipe_stdin {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run {}: {}", program, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|e| anyhow!("Failed to write to {}: {}", program, e))?;
+    }
+    let status = child.wait()?;
+    Ok(status.code().unwrap_or(1))
+}
+
+/// A command receiving transcription updates on stdin as they arrive
+pub struct StreamExec {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    last_sent: String,
+    raw: bool, // --raw: send control characters as they are
+}
+
+impl StreamExec {
+    /// Spawn the command with a piped stdin
+    pub fn spawn(cmd_args: &[String], raw: bool) -> Result<Self> {
+        let program = cmd_args
+            .first()
+            .ok_or_else(|| anyhow!("No command given after --"))?;
+        let mut child = Command::new(program)
+            .args(&cmd_args[1..])
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow!("Failed to run {}: {}", program, e))?;
+        let stdin = child.stdin.take();
+        Ok(Self {
+            child,
+            stdin,
+            last_sent: String::new(),
+            raw,
+        })
+    }
+
+    /// Send the transcription as a new line if it changed since the last update.
+    /// If the command has closed its stdin, further updates are dropped.
+    pub fn update(&mut self, text: &str) {
+        if text == self.last_sent {
+            return;
+        }
+        let Some(stdin) = self.stdin.as_mut() else {
+            return;
+        };
+
+        let line = text.replace('\n', " ");
+        let line = if self.raw {
+            Cow::Borrowed(line.as_str())
+        } else {
+            output::sanitize(&line)
+        };
+        let result = writeln!(stdin, "{}", line).and_then(|_| stdin.flush());
+        if result.is_err() {
+            // Broken pipe: the reader went away, stop streaming
+            self.stdin = None;
+        }
+        self.last_sent = text.to_string();
+    }
+
+    /// Close stdin and wait for the command to exit, returning its exit code
+    pub fn finish(mut self) -> Result<i32> {
+        drop(self.stdin.take());
+        let status = self.child.wait()?;
+        Ok(status.code().unwrap_or(1))
+    }
+}
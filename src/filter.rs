@@ -0,0 +1,79 @@
+//! Masking of filtered words (`--filter-words FILE`)
+//!
+//! The file lists one word per line; blank lines and lines starting with `#`
+//! are ignored. Matching is case-insensitive on whole words, and each
+//! matched word is replaced by asterisks of the same length. This is done
+//! here rather than by the recognizer so every backend behaves the same.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+#[derive(Clone)]
+pub struct WordFilter {
+    // Lowercased words to mask
+    words: HashSet<String>,
+}
+
+impl WordFilter {
+    /// Load the word list from a file
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        Ok(Self::from_list(&contents))
+    }
+
+    fn from_list(list: &str) -> Self {
+        let words = list
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_lowercase)
+            .collect();
+        Self { words }
+    }
+
+    /// Mask filtered words in `text`. Text without any is returned unchanged.
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut out: Option<String> = None;
+        let mut last = 0;
+
+        for (start, word) in words(text) {
+            if !self.words.contains(&word.to_lowercase()) {
+                continue;
+            }
+            let out = out.get_or_insert_with(|| String::with_capacity(text.len()));
+            out.push_str(&text[last..start]);
+            out.extend(word.chars().map(|_| '*'));
+            last = start + word.len();
+        }
+
+        match out {
+            Some(mut out) => {
+                out.push_str(&text[last..]);
+                Cow::Owned(out)
+            }
+            None => Cow::Borrowed(text),
+        }
+    }
+}
+
+/// Words in `text` with their byte offsets. Apostrophes count as part of a
+/// word so contractions are matched whole.
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '\'' || c == '’';
+    let mut rest = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        // Skip to the next word
+        while rest.next_if(|&(_, c)| !is_word_char(c)).is_some() {}
+        let (start, _) = *rest.peek()?;
+        let mut end = start;
+        while let Some((i, c)) = rest.next_if(|&(_, c)| is_word_char(c)) {
+            end = i + c.len_utf8();
+        }
+        Some((start, &text[start..end]))
+    })
+}
@@ -0,0 +1,28 @@
+//! Picking the `--grammar` phrase that was said
+//!
+//! Windows and Vosk only recognize the listed phrases, but macOS and Whisper
+//! merely favor them, and case and punctuation vary between engines. So the
+//! final transcription is matched against the list the way spoken commands
+//! are (whole words, ignoring case and punctuation) and the phrase is output
+//! as written in the file, or nothing if none was said.
+
+use crate::commands::match_key;
+
+/// The longest phrase whose words appear in order in `text`; the first one
+/// listed on a tie
+pub fn find<'a>(phrases: &'a [String], text: &str) -> Option<&'a str> {
+    let words: Vec<String> = text.split_whitespace().map(match_key).collect();
+
+    let mut best: Option<(usize, &str)> = None;
+    for phrase in phrases {
+        let phrase_words: Vec<String> = phrase.split_whitespace().map(match_key).collect();
+        let len = phrase_words.len();
+        if len == 0 || best.is_some_and(|(best_len, _)| best_len >= len) {
+            continue;
+        }
+        if words.windows(len).any(|window| window == phrase_words) {
+            best = Some((len, phrase));
+        }
+    }
+    best.map(|(_, phrase)| phrase)
+}
@@ -0,0 +1,44 @@
+//! Key hold detection for `--push-to-talk`
+//!
+//! Terminals report key presses but not releases. A held key is repeated by
+//! the keyboard, so the key counts as held while presses keep arriving and
+//! as released once they stop. The first repeat only comes after the
+//! keyboard's repeat delay, so a lone press holds for longer than a repeat.
+
+use std::time::{Duration, Instant};
+
+/// How long a first press counts as held, covering the repeat delay (660ms
+/// by default on X11, less on macOS and Windows)
+const FIRST_PRESS_HOLD: Duration = Duration::from_millis(700);
+/// How long each repeat extends the hold (repeats usually come every 30-50ms)
+const REPEAT_HOLD: Duration = Duration::from_millis(200);
+
+#[derive(Default)]
+pub struct KeyHold {
+    held_since: Option<Instant>, // First press of the current hold
+    last_press: Option<Instant>,
+}
+
+impl KeyHold {
+    /// The key was pressed, or repeated while held
+    pub fn press(&mut self) {
+        let now = Instant::now();
+        if !self.is_held() {
+            self.held_since = Some(now);
+        }
+        self.last_press = Some(now);
+    }
+
+    /// Whether the key is still held down
+    pub fn is_held(&self) -> bool {
+        let Some(last_press) = self.last_press else {
+            return false;
+        };
+        let hold = if self.held_since == Some(last_press) {
+            FIRST_PRESS_HOLD
+        } else {
+            REPEAT_HOLD
+        };
+        last_press.elapsed() < hold
+    }
+}
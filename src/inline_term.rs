@@ -10,8 +10,8 @@ use anyhow::Result;
 use termwiz::cell::{Cell, CellAttributes};
 use termwiz::color::ColorAttribute;
 use termwiz::surface::change::Change;
-use termwiz::surface::line::Line;
-use termwiz::surface::{CursorVisibility, Position};
+use termwiz::surface::line::{CellRef, Line};
+use termwiz::surface::{CursorShape, CursorVisibility, Position};
 use termwiz::terminal::Terminal;
 
 const RESIZE_DEBOUNCE_MS: u64 = 150;
@@ -26,6 +26,7 @@ pub struct InlineSurface {
     height: usize,
     lines: Vec<Line>,
     prev_lines: Vec<Line>,
+    invalidated: bool, // Every cell is drawn on the next render
 }
 
 impl InlineSurface {
@@ -38,6 +39,7 @@ impl InlineSurface {
             height,
             lines,
             prev_lines,
+            invalidated: false,
         }
     }
 
@@ -69,18 +71,28 @@ impl InlineSurface {
     }
 
     /// Set text at a position with given attributes
-    #[allow(dead_code)]
     pub fn set_text(&mut self, x: usize, y: usize, text: &str, attrs: CellAttributes) {
         if y >= self.height {
             return;
         }
-        let mut col = x;
-        for ch in text.chars() {
+        for (col, ch) in (x..).zip(text.chars()) {
             if col >= self.width {
                 break;
             }
             self.lines[y].set_cell(col, Cell::new(ch, attrs.clone()), 0);
-            col += 1;
+        }
+    }
+
+    /// Copy `count` rows of `src`, starting at `src_row`, to this surface
+    /// starting at `dst_row`
+    pub fn copy_rows(&mut self, src: &InlineSurface, src_row: usize, dst_row: usize, count: usize) {
+        for i in 0..count {
+            let (Some(line), Some(dst)) =
+                (src.lines.get(src_row + i), self.lines.get_mut(dst_row + i))
+            else {
+                break;
+            };
+            dst.clone_from(line);
         }
     }
 
@@ -92,20 +104,6 @@ impl InlineSurface {
         }
     }
 
-    /// Compute changes needed to update the terminal from the previous state.
-    /// Returns changes that use relative cursor positioning.
-    #[allow(dead_code)]
-    pub fn get_changes(&self) -> Vec<Change> {
-        let mut changes = Vec::new();
-
-        for (row, (line, prev_line)) in self.lines.iter().zip(self.prev_lines.iter()).enumerate() {
-            let line_changes = self.diff_line(row, line, prev_line);
-            changes.extend(line_changes);
-        }
-
-        changes
-    }
-
     /// Get changes for a single line (uses only absolute X positions, no Y)
     #[allow(dead_code)]
     pub fn get_line_changes(&self, row: usize) -> Vec<Change> {
@@ -153,6 +151,7 @@ impl InlineSurface {
         let mut col = 0;
         let mut cursor_col: Option<usize> = None;
         let mut current_attrs: Option<CellAttributes> = None;
+        let blank = CellAttributes::default();
 
         let cells: Vec<_> = line.visible_cells().collect();
         let prev_cells: Vec<_> = prev_line.visible_cells().collect();
@@ -161,95 +160,44 @@ impl InlineSurface {
             let cell = cells.get(col);
             let prev_cell = prev_cells.get(col);
 
-            // Check if cells differ
-            let differs = match (cell, prev_cell) {
-                (Some(c), Some(p)) => !c.same_contents(p),
-                (Some(_), None) | (None, Some(_)) => true,
-                (None, None) => false,
-            };
+            // Check if cells differ; trailing blanks may have been pruned
+            // from either line
+            let is_blank = |c: &CellRef| c.str() == " " && *c.attrs() == blank;
+            let differs = self.invalidated
+                || match (cell, prev_cell) {
+                    (Some(c), Some(p)) => !c.same_contents(p),
+                    (Some(c), None) | (None, Some(c)) => !is_blank(c),
+                    (None, None) => false,
+                };
 
             if differs {
-                if let Some(c) = cell {
-                    // Position cursor if needed (only X)
-                    if cursor_col != Some(col) {
-                        changes.push(Change::CursorPosition {
-                            x: Position::Absolute(col),
-                            y: Position::Relative(0),
-                        });
-                    }
-
-                    // Update attributes if needed
-                    let cell_attrs = c.attrs();
-                    let need_attrs = match &current_attrs {
-                        Some(a) => a != cell_attrs,
-                        None => *cell_attrs != CellAttributes::default(),
-                    };
-                    if need_attrs {
-                        changes.push(Change::AllAttributes(cell_attrs.clone()));
-                        current_attrs = Some(cell_attrs.clone());
-                    }
-
-                    // Add text
-                    changes.push(Change::Text(c.str().to_string()));
-                    cursor_col = Some(col + c.width().max(1));
+                // Position cursor if needed (only X)
+                if cursor_col != Some(col) {
+                    changes.push(Change::CursorPosition {
+                        x: Position::Absolute(col),
+                        y: Position::Relative(0),
+                    });
                 }
-            }
-
-            col += 1;
-        }
-
-        changes
-    }
-
-    /// Diff a single line against its previous state (legacy, includes Y)
-    #[allow(dead_code)]
-    fn diff_line(&self, row: usize, line: &Line, prev_line: &Line) -> Vec<Change> {
-        let mut changes = Vec::new();
-        let mut col = 0;
-        let mut need_position = true;
-        let mut current_attrs: Option<CellAttributes> = None;
 
-        let cells: Vec<_> = line.visible_cells().collect();
-        let prev_cells: Vec<_> = prev_line.visible_cells().collect();
-
-        while col < self.width {
-            let cell = cells.get(col);
-            let prev_cell = prev_cells.get(col);
-
-            // Check if cells differ
-            let differs = match (cell, prev_cell) {
-                (Some(c), Some(p)) => !c.same_contents(p),
-                (Some(_), None) | (None, Some(_)) => true,
-                (None, None) => false,
-            };
+                // A cell past the end of the line is drawn blank
+                let (text, cell_attrs, width) = match cell {
+                    Some(c) => (c.str(), c.attrs(), c.width()),
+                    None => (" ", &blank, 1),
+                };
 
-            if differs {
-                if let Some(c) = cell {
-                    // Need to position cursor
-                    if need_position {
-                        changes.push(Change::CursorPosition {
-                            x: Position::Absolute(col),
-                            y: Position::Absolute(row),
-                        });
-                        need_position = false;
-                    }
-
-                    // Update attributes if needed
-                    let cell_attrs = c.attrs();
-                    let need_attrs = match &current_attrs {
-                        Some(a) => a != cell_attrs,
-                        None => true,
-                    };
-                    if need_attrs {
-                        changes.push(Change::AllAttributes(cell_attrs.clone()));
-                        current_attrs = Some(cell_attrs.clone());
-                    }
-
-                    // Add text
-                    changes.push(Change::Text(c.str().to_string()));
+                // Update attributes if needed
+                let need_attrs = match &current_attrs {
+                    Some(a) => a != cell_attrs,
+                    None => *cell_attrs != CellAttributes::default(),
+                };
+                if need_attrs {
+                    changes.push(Change::AllAttributes(cell_attrs.clone()));
+                    current_attrs = Some(cell_attrs.clone());
                 }
-            } else {
-                need_position = true;
+
+                // Add text
+                changes.push(Change::Text(text.to_string()));
+                cursor_col = Some(col + width.max(1));
             }
 
             col += 1;
@@ -261,46 +209,12 @@ impl InlineSurface {
     /// Commit changes - copy current state to previous state
     pub fn commit(&mut self) {
         self.prev_lines.clone_from(&self.lines);
+        self.invalidated = false;
     }
 
     /// Force a full repaint on next render
     pub fn invalidate(&mut self) {
-        for line in &mut self.prev_lines {
-            line.fill_range(
-                0..self.width,
-                &Cell::new('\x00', CellAttributes::default()),
-                0,
-            );
-        }
-    }
-
-    /// Get a full repaint (all content, no diffing)
-    #[allow(dead_code)]
-    pub fn get_full_repaint(&self) -> Vec<Change> {
-        let mut changes = Vec::new();
-        let mut current_attrs: Option<CellAttributes> = None;
-
-        for (row, line) in self.lines.iter().enumerate() {
-            changes.push(Change::CursorPosition {
-                x: Position::Absolute(0),
-                y: Position::Absolute(row),
-            });
-
-            for cell in line.visible_cells() {
-                let cell_attrs = cell.attrs();
-                let need_attrs = match &current_attrs {
-                    Some(a) => a != cell_attrs,
-                    None => *cell_attrs != CellAttributes::default(),
-                };
-                if need_attrs {
-                    changes.push(Change::AllAttributes(cell_attrs.clone()));
-                    current_attrs = Some(cell_attrs.clone());
-                }
-                changes.push(Change::Text(cell.str().to_string()));
-            }
-        }
-
-        changes
+        self.invalidated = true;
     }
 }
 
@@ -311,6 +225,9 @@ pub struct InlineTerminal<T: Terminal> {
     rendered_height: usize, // Height of region we've rendered
     cursor_row: usize,      // Row cursor is at after render (0 = top of region)
     pending_resize: Option<(usize, Instant)>, // (new_width, detected_at) for debouncing
+    screen_rows: usize,     // Terminal height
+    cursor_shape: CursorShape, // Shape of the cursor shown by render_with_cursor
+    shape_set: bool,        // The terminal's cursor shape was changed from its default
 }
 
 impl<T: Terminal> InlineTerminal<T> {
@@ -326,9 +243,18 @@ impl<T: Terminal> InlineTerminal<T> {
             rendered_height: 0,
             cursor_row: 0,
             pending_resize: None,
+            screen_rows: size.rows,
+            cursor_shape: CursorShape::BlinkingBar,
+            shape_set: false,
         })
     }
 
+    /// Set the shape of the cursor shown while editing (a blinking bar by
+    /// default; the terminal decides the blink rate)
+    pub fn set_cursor_shape(&mut self, shape: CursorShape) {
+        self.cursor_shape = shape;
+    }
+
     /// Get mutable access to the terminal
     pub fn terminal(&mut self) -> &mut T {
         &mut self.terminal
@@ -339,50 +265,59 @@ impl<T: Terminal> InlineTerminal<T> {
         &mut self.surface
     }
 
-    /// Check for terminal resize and update surface width (debounced)
+    /// Terminal height in rows
+    pub fn screen_rows(&self) -> usize {
+        self.screen_rows
+    }
+
+    /// Record a terminal size change reported by the terminal (SIGWINCH on
+    /// Unix, console events on Windows). A width change is applied by
+    /// `check_for_resize` once it has settled; the height right away.
+    pub fn notify_resize(&mut self, cols: usize, rows: usize) {
+        self.screen_rows = rows;
+        let (current_width, _) = self.surface.dimensions();
+        let target_width = self.pending_resize.map(|(w, _)| w).unwrap_or(current_width);
+        if cols != target_width {
+            self.pending_resize = Some((cols, Instant::now()));
+        }
+    }
+
+    /// Apply a pending width change once it has settled (debounced).
+    /// The surface is resized and invalidated so content is reflowed at the
+    /// new width on the next render.
     /// Returns true if resize was applied, false otherwise.
     /// Use `is_resizing()` to check if rendering should be paused.
     pub fn check_for_resize(&mut self) -> Result<bool> {
-        let size = self
-            .terminal
-            .get_screen_size()
-            .map_err(|e| anyhow::anyhow!("{}", e))?;
-        let (current_width, height) = self.surface.dimensions();
-
-        // Check if terminal width changed from what we're rendering at
-        let target_width = self.pending_resize.map(|(w, _)| w).unwrap_or(current_width);
-        if size.cols != target_width {
-            // Width changed - record/update the pending resize
-            self.pending_resize = Some((size.cols, Instant::now()));
-            return Ok(false); // Don't act yet, wait for debounce
+        let Some((new_width, detected_at)) = self.pending_resize else {
+            return Ok(false);
+        };
+        if detected_at.elapsed() < Duration::from_millis(RESIZE_DEBOUNCE_MS) {
+            return Ok(false);
         }
 
-        // Check if we have a pending resize that's settled
-        if let Some((new_width, detected_at)) = self.pending_resize {
-            if detected_at.elapsed() >= Duration::from_millis(RESIZE_DEBOUNCE_MS) {
-                // Resize has settled - apply it
-                self.pending_resize = None;
-
-                // Scroll past any mess by printing newlines, then start fresh
-                if self.rendered_height > 0 {
-                    let mut changes = Vec::new();
-                    for _ in 0..self.rendered_height {
-                        changes.push(Change::Text("\n".to_string()));
-                    }
-                    self.terminal
-                        .render(&changes)
-                        .map_err(|e| anyhow::anyhow!("{}", e))?;
-                }
+        // Resize has settled - apply it
+        self.pending_resize = None;
+        let (current_width, height) = self.surface.dimensions();
+        if new_width == current_width {
+            return Ok(false);
+        }
 
-                self.surface.resize(new_width, height);
-                self.surface.invalidate();
-                self.rendered_height = 0;
-                self.cursor_row = 0;
-                return Ok(true);
+        // Scroll past any mess by printing newlines, then start fresh
+        if self.rendered_height > 0 {
+            let mut changes = Vec::new();
+            for _ in 0..self.rendered_height {
+                changes.push(Change::Text("\n".to_string()));
             }
+            self.terminal
+                .render(&changes)
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
         }
 
-        Ok(false)
+        self.surface.resize(new_width, height);
+        self.surface.invalidate();
+        self.rendered_height = 0;
+        self.cursor_row = 0;
+        Ok(true)
     }
 
     /// Returns true if a resize is pending (rendering should be paused)
@@ -485,6 +420,10 @@ impl<T: Terminal> InlineTerminal<T> {
                 x: Position::Absolute(col),
                 y: Position::Relative(-(rows_back as isize)),
             });
+            if !self.shape_set {
+                changes.push(Change::CursorShape(self.cursor_shape));
+                self.shape_set = true;
+            }
             changes.push(Change::CursorVisibility(CursorVisibility::Visible));
             row
         } else {
@@ -505,14 +444,57 @@ impl<T: Terminal> InlineTerminal<T> {
         Ok(())
     }
 
+    /// Hand the terminal to another program (e.g. an external editor): clear
+    /// our region and leave raw mode
+    pub fn suspend(&mut self) -> Result<()> {
+        let mut changes = self.clear_region();
+        // The other program gets the cursor shape the user had
+        self.restore_cursor(&mut changes);
+        self.terminal
+            .render(&changes)
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        self.terminal
+            .set_cooked_mode()
+            .map_err(|e| anyhow::anyhow!("{}", e))
+    }
+
+    /// Take the terminal back after `suspend`. The other program may have
+    /// moved the cursor or left output behind, so the next render starts a
+    /// fresh region at the cursor and repaints every cell.
+    pub fn resume(&mut self) -> Result<()> {
+        self.terminal
+            .set_raw_mode()
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        self.rendered_height = 0;
+        self.cursor_row = 0;
+        self.surface.invalidate();
+        Ok(())
+    }
+
+    /// Ring the terminal bell
+    pub fn bell(&mut self) -> Result<()> {
+        self.terminal
+            .render(&[Change::Text("\x07".to_string())])
+            .map_err(|e| anyhow::anyhow!("{}", e))
+    }
+
     /// Render without cursor (convenience method)
-    #[allow(dead_code)]
     pub fn render(&mut self) -> Result<()> {
         self.render_with_cursor(None)
     }
 
     /// Clean up - clear our rendering region and show cursor
     pub fn cleanup(&mut self) -> Result<()> {
+        let mut changes = self.clear_region();
+        self.restore_cursor(&mut changes);
+        self.terminal
+            .render(&changes)
+            .map_err(|e| anyhow::anyhow!("{}", e))
+    }
+
+    /// Changes that clear our rendering region, leaving the cursor at its
+    /// start; the next render starts a fresh region there
+    fn clear_region(&mut self) -> Vec<Change> {
         let mut changes = Vec::new();
 
         // Move to start of our region (from current cursor_row)
@@ -542,15 +524,152 @@ impl<T: Terminal> InlineTerminal<T> {
             });
         }
 
-        // Show cursor
-        changes.push(Change::CursorVisibility(CursorVisibility::Visible));
-
-        self.terminal
-            .render(&changes)
-            .map_err(|e| anyhow::anyhow!("{}", e))?;
         self.rendered_height = 0;
         self.cursor_row = 0;
+        changes
+    }
 
-        Ok(())
+    /// Show the cursor, in the shape it had before `render_with_cursor`
+    /// changed it
+    fn restore_cursor(&mut self, changes: &mut Vec<Change>) {
+        if self.shape_set {
+            changes.push(Change::CursorShape(CursorShape::Default));
+            self.shape_set = false;
+        }
+        changes.push(Change::CursorVisibility(CursorVisibility::Visible));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Where the diff moves the cursor to draw a changed cell
+    fn at(col: usize) -> Change {
+        Change::CursorPosition {
+            x: Position::Absolute(col),
+            y: Position::Relative(0),
+        }
+    }
+
+    fn text(s: &str) -> Change {
+        Change::Text(s.to_string())
+    }
+
+    /// A committed surface showing `line` on its first row
+    fn committed(width: usize, line: &str) -> InlineSurface {
+        let mut surface = InlineSurface::new(width, 2);
+        surface.set_text(0, 0, line, CellAttributes::default());
+        surface.commit();
+        surface
+    }
+
+    #[test]
+    fn unchanged_line_has_no_changes() {
+        let surface = committed(10, "hello");
+        assert_eq!(surface.get_line_changes(0), vec![]);
+        assert_eq!(surface.get_line_changes(1), vec![]);
+    }
+
+    #[test]
+    fn only_changed_cells_are_drawn() {
+        let mut surface = committed(10, "hello");
+        surface.set_text(1, 0, "a", CellAttributes::default());
+        surface.set_text(4, 0, "!", CellAttributes::default());
+        assert_eq!(
+            surface.get_line_changes(0),
+            vec![at(1), text("a"), at(4), text("!")]
+        );
+        // Other rows are untouched
+        assert_eq!(surface.get_line_changes(1), vec![]);
+    }
+
+    #[test]
+    fn adjacent_changes_share_one_cursor_move() {
+        // The space lands on a blank cell, so drawing starts after it
+        let mut surface = committed(10, "hello");
+        surface.set_text(5, 0, " you", CellAttributes::default());
+        assert_eq!(
+            surface.get_line_changes(0),
+            vec![at(6), text("y"), text("o"), text("u")]
+        );
+    }
+
+    #[test]
+    fn erased_cells_are_drawn_blank() {
+        let mut surface = committed(10, "hello");
+        surface.clear_to_eol(3, 0);
+        assert_eq!(
+            surface.get_line_changes(0),
+            vec![at(3), text(" "), text(" ")]
+        );
+    }
+
+    #[test]
+    fn attribute_change_is_drawn_with_its_attributes() {
+        let mut surface = committed(10, "hello");
+        let mut bold = CellAttributes::default();
+        bold.set_intensity(termwiz::cell::Intensity::Bold);
+        surface.set_text(0, 0, "h", bold.clone());
+        assert_eq!(
+            surface.get_line_changes(0),
+            vec![at(0), Change::AllAttributes(bold), text("h")]
+        );
+    }
+
+    #[test]
+    fn commit_makes_changes_current() {
+        let mut surface = committed(10, "hello");
+        surface.set_text(0, 0, "j", CellAttributes::default());
+        surface.commit();
+        assert_eq!(surface.get_line_changes(0), vec![]);
+    }
+
+    #[test]
+    fn shrinking_repaints_every_cell() {
+        let mut surface = committed(10, "hello");
+        // As check_for_resize does when the terminal gets narrower
+        surface.resize(4, 2);
+        surface.invalidate();
+        surface.set_text(0, 0, "hell", CellAttributes::default());
+        assert_eq!(
+            surface.get_line_changes(0),
+            vec![at(0), text("h"), text("e"), text("l"), text("l")]
+        );
+        // Blank rows are repainted too, and nothing beyond the new width
+        assert_eq!(
+            surface.get_line_changes(1),
+            vec![at(0), text(" "), text(" "), text(" "), text(" ")]
+        );
+    }
+
+    #[test]
+    fn tiny_widths_clip_text() {
+        for width in 0..=2 {
+            let mut surface = InlineSurface::new(width, 2);
+            surface.set_text(0, 0, "hello", CellAttributes::default());
+            surface.set_cell(width, 1, Cell::new('x', CellAttributes::default()));
+            let drawn: Vec<_> = ["h", "e"][..width].iter().map(|s| text(s)).collect();
+            assert_eq!(surface.get_line_content(0), drawn, "width {width}");
+
+            let mut changes = vec![];
+            if width > 0 {
+                changes.push(at(0));
+                changes.extend(drawn);
+            }
+            assert_eq!(surface.get_line_changes(0), changes, "width {width}");
+            // Nothing was set past the edge
+            assert_eq!(surface.get_line_changes(1), vec![]);
+
+            // A full repaint is only as wide as the surface
+            surface.resize(width, 1);
+            surface.invalidate();
+            let mut repaint = vec![];
+            if width > 0 {
+                repaint.push(at(0));
+                repaint.extend((0..width).map(|_| text(" ")));
+            }
+            assert_eq!(surface.get_line_changes(0), repaint, "width {width}");
+        }
     }
 }
@@ -0,0 +1,241 @@
+//! Configurable key bindings for recording mode
+//!
+//! Chords are written like `enter`, `esc`, `ctrl+e`, `ctrl+shift+e` or `f2`
+//! and bound to actions in the `[keys]` table of the config file:
+//!
+//! ```toml
+//! [keys]
+//! finish = ["enter", "esc"]
+//! cancel = "ctrl+c"
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use termwiz::input::{KeyCode, KeyEvent, Modifiers};
+
+use crate::config::KeyBinding;
+
+/// Something a key can do while recording
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    Finish,
+    Cancel,
+    Restart,
+    ClearLive,
+    Edit,
+    ExternalEditor,
+    Pause,
+    Talk,
+    Status,
+}
+
+impl Action {
+    const ALL: [Action; 9] = [
+        Action::Finish,
+        Action::Cancel,
+        Action::Restart,
+        Action::ClearLive,
+        Action::Edit,
+        Action::ExternalEditor,
+        Action::Pause,
+        Action::Talk,
+        Action::Status,
+    ];
+
+    /// Name used in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Finish => "finish",
+            Action::Cancel => "cancel",
+            Action::Restart => "restart",
+            Action::ClearLive => "clear_live",
+            Action::Edit => "edit",
+            Action::ExternalEditor => "external_editor",
+            Action::Pause => "pause",
+            Action::Talk => "talk",
+            Action::Status => "status",
+        }
+    }
+
+    fn default_chords(self) -> &'static [&'static str] {
+        match self {
+            Action::Finish => &["enter"],
+            Action::Cancel => &["ctrl+c"],
+            Action::Restart => &["ctrl+d"],
+            Action::ClearLive => &["ctrl+l"],
+            Action::Edit => &["ctrl+e"],
+            Action::ExternalEditor => &["ctrl+shift+e"],
+            Action::Pause => &["ctrl+p"],
+            Action::Talk => &["space"],
+            Action::Status => &["ctrl+t"],
+        }
+    }
+}
+
+/// A key plus modifiers
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KeyChord {
+    key: KeyCode,
+    modifiers: Modifiers,
+}
+
+impl KeyChord {
+    fn matches(&self, event: &KeyEvent) -> bool {
+        self.key == event.key && self.modifiers == event.modifiers
+    }
+
+    /// Whether terminals report the chord as such. Most send a plain CR or
+    /// tab for Enter and Tab with Ctrl or Shift, which arrives as the bare
+    /// key.
+    fn is_reported(&self) -> bool {
+        !matches!(self.key, KeyCode::Enter | KeyCode::Tab)
+            || !self
+                .modifiers
+                .intersects(Modifiers::CTRL | Modifiers::SHIFT)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut modifiers = Modifiers::NONE;
+        let mut key = None;
+
+        for part in s.split('+').map(|p| p.trim().to_ascii_lowercase()) {
+            if key.is_some() {
+                return Err(anyhow!("Invalid key chord '{}': key must come last", s));
+            }
+            match part.as_str() {
+                "ctrl" | "control" => modifiers |= Modifiers::CTRL,
+                "shift" => modifiers |= Modifiers::SHIFT,
+                "alt" | "meta" => modifiers |= Modifiers::ALT,
+                "enter" | "return" => key = Some(KeyCode::Enter),
+                "esc" | "escape" => key = Some(KeyCode::Escape),
+                "tab" => key = Some(KeyCode::Tab),
+                "space" => key = Some(KeyCode::Char(' ')),
+                "backspace" => key = Some(KeyCode::Backspace),
+                "delete" | "del" => key = Some(KeyCode::Delete),
+                f if f.len() > 1 && f.starts_with('f') && f[1..].parse::<u8>().is_ok() => {
+                    key = Some(KeyCode::Function(f[1..].parse().unwrap()));
+                }
+                c if c.chars().count() == 1 => {
+                    key = Some(KeyCode::Char(c.chars().next().unwrap()));
+                }
+                _ => return Err(anyhow!("Invalid key chord '{}': unknown key '{}'", s, part)),
+            }
+        }
+
+        let mut key = key.ok_or_else(|| anyhow!("Invalid key chord '{}': missing key", s))?;
+
+        // Terminals report shifted letters as uppercase
+        if let KeyCode::Char(c) = key {
+            if modifiers.contains(Modifiers::SHIFT) && c.is_ascii_alphabetic() {
+                key = KeyCode::Char(c.to_ascii_uppercase());
+            }
+        }
+
+        Ok(Self { key, modifiers })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    /// Compact form for the controls bar (e.g. "^E", "^⇧E", "Enter")
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(Modifiers::CTRL) {
+            write!(f, "^")?;
+        }
+        if self.modifiers.contains(Modifiers::ALT) {
+            write!(f, "M-")?;
+        }
+        if self.modifiers.contains(Modifiers::SHIFT) {
+            write!(f, "⇧")?;
+        }
+        match self.key {
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Escape => write!(f, "Esc"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::Backspace => write!(f, "Bksp"),
+            KeyCode::Delete => write!(f, "Del"),
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::Function(n) => write!(f, "F{}", n),
+            _ => write!(f, "?"),
+        }
+    }
+}
+
+/// Active bindings for recording mode
+pub struct Keymap {
+    bindings: Vec<(Action, KeyChord)>,
+}
+
+impl Keymap {
+    /// Build the keymap from config overrides on top of the defaults.
+    /// Fails on unknown actions, invalid chords, or a chord bound twice.
+    pub fn from_config(keys: &HashMap<String, KeyBinding>) -> Result<Self> {
+        for name in keys.keys() {
+            if !Action::ALL.iter().any(|a| a.name() == name) {
+                return Err(anyhow!("Unknown action in [keys]: {}", name));
+            }
+        }
+
+        let mut bindings: Vec<(Action, KeyChord)> = Vec::new();
+        for action in Action::ALL {
+            let chords = match keys.get(action.name()) {
+                Some(binding) => binding.chords(),
+                None => action.default_chords().to_vec(),
+            };
+            for chord in chords {
+                let chord: KeyChord = chord.parse()?;
+                if let Some((other, _)) = bindings.iter().find(|(_, c)| *c == chord) {
+                    return Err(anyhow!(
+                        "Key {} is bound to both '{}' and '{}'",
+                        chord,
+                        other.name(),
+                        action.name()
+                    ));
+                }
+                if !chord.is_reported() {
+                    log::warn!(
+                        "Most terminals send {} as plain {}; '{}' may never see it",
+                        chord,
+                        KeyChord {
+                            modifiers: Modifiers::NONE,
+                            ..chord
+                        },
+                        action.name()
+                    );
+                }
+                bindings.push((action, chord));
+            }
+        }
+
+        Ok(Self { bindings })
+    }
+
+    /// Action bound to a key event, if any
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, chord)| chord.matches(event))
+            .map(|(action, _)| *action)
+    }
+
+    /// First chord bound to an action that terminals report, for display
+    pub fn chord(&self, action: Action) -> Option<KeyChord> {
+        self.bindings
+            .iter()
+            .find(|(a, chord)| *a == action && chord.is_reported())
+            .map(|(_, chord)| *chord)
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_config(&HashMap::new()).expect("default keymap is valid")
+    }
+}
@@ -0,0 +1,32 @@
+//! Claudio - speech capture using the platform's native recognizer
+//!
+//! The `claudio` binary is a terminal UI on top of this library. To embed
+//! dictation in another program, start a [`Session`] and read transcription
+//! updates from its channel:
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use claudio::{RecognizerOptions, Session};
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let session = Session::start(RecognizerOptions::default())?;
+//!
+//!     // Each update is the full transcription so far
+//!     while let Ok(text) = session.updates().recv_timeout(Duration::from_secs(5)) {
+//!         println!("{}", text);
+//!     }
+//!
+//!     println!("Final: {}", session.finish());
+//!     Ok(())
+//! }
+//! ```
+//!
+//! For lower-level control (restarting, polling readiness), use
+//! [`SpeechRecognizer`] with a [`SharedState`] directly.
+
+mod session;
+pub mod speech;
+
+pub use session::Session;
+pub use speech::{RecognizerOptions, SharedState, SpeechRecognizer};
@@ -0,0 +1,66 @@
+//! Debug logging
+//!
+//! Off unless `--verbose` is given or `CLAUDIO_LOG` (falling back to
+//! `RUST_LOG`) sets a filter, e.g. `CLAUDIO_LOG=debug`. While the terminal UI
+//! is active stderr usually shares the terminal, so logs are written to
+//! `claudio-<pid>.log` in the per-user state directory (see
+//! `config::state_dir`) instead; headless runs log to stderr. `--log-file`
+//! overrides the destination.
+
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::process;
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+use anyhow::{anyhow, Result};
+use env_logger::{Builder, Target};
+
+use crate::config;
+
+/// Filter used by `--verbose` when no environment filter is set
+const VERBOSE_FILTER: &str = "claudio=debug";
+
+/// Install the logger if logging was requested. Returns the log file path
+/// when logs go to a file.
+pub fn init(verbose: bool, log_file: Option<&Path>, headless: bool) -> Result<Option<PathBuf>> {
+    let filter = env::var("CLAUDIO_LOG")
+        .or_else(|_| env::var("RUST_LOG"))
+        .ok()
+        .or_else(|| verbose.then(|| VERBOSE_FILTER.to_string()));
+    let Some(filter) = filter else {
+        return Ok(None);
+    };
+
+    let mut builder = Builder::new();
+    builder.parse_filters(&filter);
+    let path = match log_file {
+        Some(path) => {
+            let file = File::create(path)
+                .map_err(|e| anyhow!("Failed to open log file {}: {}", path.display(), e))?;
+            builder.target(Target::Pipe(Box::new(file)));
+            Some(path.to_path_buf())
+        }
+        None if headless => None,
+        None => {
+            // One per run, so instances running side by side keep their own
+            let path = config::state_dir()?.join(format!("claudio-{}.log", process::id()));
+            let mut options = OpenOptions::new();
+            options.write(true).create(true).truncate(true);
+            #[cfg(unix)]
+            options.mode(0o600);
+            let file = options
+                .open(&path)
+                .map_err(|e| anyhow!("Failed to open log file {}: {}", path.display(), e))?;
+            builder.target(Target::Pipe(Box::new(file)));
+            Some(path)
+        }
+    };
+    builder
+        .try_init()
+        .map_err(|e| anyhow!("Failed to initialize logging: {}", e))?;
+
+    Ok(path)
+}
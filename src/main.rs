@@ -3,181 +3,789 @@
 //! A CLI tool that listens via microphone and transcribes speech in real-time.
 
 use std::{
-    env,
-    io::Write,
-    process::Command,
+    fs,
+    io::IsTerminal,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc, Mutex,
+        Arc,
     },
     time::{Duration, Instant},
 };
 
 use anyhow::Result;
+use claudio::speech::Engine;
+use claudio::{RecognizerOptions, SharedState, SpeechRecognizer};
 use termwiz::caps::Capabilities;
 use termwiz::input::{InputEvent, KeyCode, Modifiers};
+use termwiz::surface::CursorShape;
 use termwiz::terminal::{SystemTerminal, Terminal};
 
+mod autosave;
+mod autostop;
+mod cli;
+mod clipboard;
+mod commands;
+mod config;
+mod confirm;
+mod debounce;
+mod editor;
+mod exec;
+mod filter;
+mod grammar;
+mod hold;
 mod inline_term;
-mod speech;
+mod keymap;
+mod logging;
+mod messages;
+mod output;
+mod sink;
+mod stats;
 mod ui;
+mod webhook;
 
+use autosave::Autosave;
+use autostop::{AutoStop, LimitPolicy, StopReason};
+use cli::{Args, Subcommand};
+use commands::VoiceCommands;
+use config::Config;
+use debounce::Debounce;
+use exec::StreamExec;
+use filter::WordFilter;
+use hold::KeyHold;
 use inline_term::InlineTerminal;
-use speech::SpeechRecognizer;
-use ui::{Mode, SpinnerState, Ui};
+use keymap::{Action, Keymap};
+use messages::Messages;
+use output::LineStream;
+use sink::Sinks;
+use stats::Stats;
+use ui::{Background, ColorMode, Glyphs, Mode, SpinnerState, Ui};
 
 struct App {
-    transcription: Arc<Mutex<String>>,
-    is_listening: Arc<AtomicBool>,
-    is_ready: Arc<AtomicBool>,
+    shared: SharedState,
     should_quit: bool,
     exit_code: i32,
     start_time: Instant,
+    listening_since: Instant, // Start of the current recognition session
     recognizer: Option<SpeechRecognizer>,
-    edit_original: String, // Saved text when entering edit mode
-}
-
-/// Open text in external editor, returns edited text
-fn open_editor(text: &str) -> Result<String> {
-    use std::fs;
-    use std::io::Read;
-
-    // Create temporary file
-    let tmp_dir = env::temp_dir();
-    let tmp_path = tmp_dir.join(format!("claudio-{}.txt", std::process::id()));
-    fs::write(&tmp_path, text)?;
-
-    // Determine editor: $VISUAL > $EDITOR > platform defaults
-    let editor = env::var("VISUAL")
-        .or_else(|_| env::var("EDITOR"))
-        .unwrap_or_else(|_| {
-            if cfg!(target_os = "windows") {
-                "notepad".to_string()
-            } else {
-                "vi".to_string()
-            }
-        });
-
-    // Open editor
-    let status = Command::new(&editor).arg(&tmp_path).status()?;
-
-    if !status.success() {
-        fs::remove_file(&tmp_path)?;
-        return Err(anyhow::anyhow!("Editor exited with non-zero status"));
-    }
-
-    // Read edited content
-    let mut file = fs::File::open(&tmp_path)?;
-    let mut edited = String::new();
-    file.read_to_string(&mut edited)?;
-
-    // Clean up
-    fs::remove_file(&tmp_path)?;
-
-    Ok(edited.trim_end().to_string())
+    recognizer_options: RecognizerOptions,
+    auto_stop: AutoStop,
+    stop_reason: Option<StopReason>, // Set when a stop condition ended the session
+    max_chars: Option<usize>,
+    max_chars_policy: LimitPolicy,
+    keymap: Keymap,
+    commands: VoiceCommands,
+    live_filter: Option<WordFilter>, // --filter-words with --filter-live
+    messages: Messages,              // UI strings in the selected language
+    paused: bool,
+    talk: Option<KeyHold>,           // --push-to-talk key state
+    show_status: bool,               // Engine status on the controls bar (--show-status)
+    continuous: bool,                // Listen again when the recognizer ends a session
+    stream: Option<StreamExec>,      // --stream-exec child receiving live updates
+    line_stream: Option<LineStream>, // --stream: finished phrases printed as they come
+    stats: Stats,
+    terminated: Arc<AtomicBool>, // SIGTERM or SIGHUP: finish like Enter
 }
 
 impl App {
-    fn new() -> Self {
+    fn new(args: &Args, keymap: Keymap, commands: VoiceCommands) -> Self {
+        let mut auto_stop = AutoStop::new(args.silence_timeout, args.max_duration);
+        if args.subcommand == Some(Subcommand::Once) {
+            auto_stop = auto_stop.after_speech();
+        }
+        // --push-to-talk: no audio until the key is held
+        let shared = SharedState::default();
+        shared.muted.store(args.push_to_talk, Ordering::SeqCst);
         Self {
-            transcription: Arc::new(Mutex::new(String::new())),
-            is_listening: Arc::new(AtomicBool::new(false)),
-            is_ready: Arc::new(AtomicBool::new(false)),
+            shared,
             should_quit: false,
             exit_code: 0,
             start_time: Instant::now(),
+            listening_since: Instant::now(),
             recognizer: None,
-            edit_original: String::new(),
+            recognizer_options: args.recognizer.clone(),
+            auto_stop,
+            stop_reason: None,
+            max_chars: args.max_chars,
+            max_chars_policy: args.max_chars_policy,
+            keymap,
+            commands,
+            live_filter: None,
+            messages: Messages::default(),
+            paused: args.start_paused,
+            talk: args.push_to_talk.then(KeyHold::default),
+            show_status: args.show_status,
+            continuous: args.continuous,
+            stream: None,
+            line_stream: None,
+            stats: Stats::new(),
+            terminated: Arc::new(AtomicBool::new(false)),
         }
     }
 
     fn start_listening(&mut self) -> Result<()> {
-        let transcription = Arc::clone(&self.transcription);
-        let is_listening = Arc::clone(&self.is_listening);
-        let is_ready = Arc::clone(&self.is_ready);
-
+        log::debug!("Starting recognizer");
+        // Fully tear down the previous session before creating the next, so
+        // its callbacks and audio device can't race the new one
+        if let Some(mut previous) = self.recognizer.take() {
+            previous.stop();
+        }
+        *self.shared.error.lock().unwrap() = None;
+        self.shared.committed_len.store(0, Ordering::SeqCst);
+        self.shared.completion.reset();
+        self.shared.audio.reset();
+        self.listening_since = Instant::now();
         self.recognizer = Some(SpeechRecognizer::new(
-            transcription,
-            is_listening,
-            is_ready,
+            self.shared.clone(),
+            self.recognizer_options.clone(),
         )?);
         self.recognizer.as_mut().unwrap().start()?;
         Ok(())
     }
 
+    /// Start a fresh recognition session after editing. The live transcription
+    /// was folded into the UI's frozen text, so it must not be shown again.
+    fn resume_listening(&mut self) -> Result<()> {
+        self.paused = false;
+        self.shared.transcription.lock().unwrap().clear();
+        self.start_listening()
+    }
+
+    /// Current transcription with spoken commands (and a live word filter)
+    /// applied
+    fn transcription(&self) -> String {
+        let text = self.shared.transcription.lock().unwrap();
+        self.for_display(&text)
+    }
+
+    /// The finished phrases at the start of the transcription, processed as
+    /// above
+    fn committed_transcription(&self) -> String {
+        let text = self.shared.transcription.lock().unwrap();
+        // Stored after the text, so it never runs ahead of what's read here
+        let len = self.shared.committed_len.load(Ordering::SeqCst);
+        self.for_display(text.get(..len).unwrap_or_default())
+    }
+
+    /// Take the transcription (processed as above), leaving it empty
+    fn take_transcription(&self) -> String {
+        let text = std::mem::take(&mut *self.shared.transcription.lock().unwrap());
+        self.for_display(&text)
+    }
+
+    fn for_display(&self, text: &str) -> String {
+        let text = self.commands.apply(text);
+        match &self.live_filter {
+            Some(filter) => filter.apply(&text).into_owned(),
+            None => text.into_owned(),
+        }
+    }
+
+    /// Engine, language (once the backend reports it) and whether
+    /// recognition runs on this machine, for the controls bar
+    fn engine_status(&self) -> Vec<String> {
+        let engine = self.recognizer_options.engine;
+        let mut status = vec![engine.resolve().name().to_string()];
+        status.extend(self.shared.locale.lock().unwrap().clone());
+        status.push(if engine.on_device() {
+            self.messages.on_device.clone()
+        } else {
+            self.messages.server.clone()
+        });
+        status
+    }
+
+    /// Whether the recognizer ended the session by itself (a final result
+    /// or its time limit) rather than being stopped or failing
+    fn session_ended(&self) -> bool {
+        !self.paused
+            && self.recognizer.is_some()
+            && self.shared.completion.is_done()
+            && self.shared.error.lock().unwrap().is_none()
+    }
+
+    /// Whether recording hasn't started yet (`--start-paused`)
+    fn waiting_to_start(&self) -> bool {
+        self.paused && self.recognizer.is_none()
+    }
+
+    /// Whether the microphone has delivered nothing for `NO_AUDIO_TIMEOUT`
+    /// since listening started (muted mic or wrong input device)
+    fn no_audio(&self) -> bool {
+        !self.paused
+            && self.shared.audio.last().is_none()
+            && self.listening_since.elapsed() >= NO_AUDIO_TIMEOUT
+    }
+
+    /// Whether `chars` characters of text have reached `--max-chars`
+    fn at_char_limit(&self, chars: usize) -> bool {
+        self.max_chars.is_some_and(|max| chars >= max)
+    }
+
+    /// The stop reason once `chars` characters reach `--max-chars`, unless
+    /// the policy is to truncate
+    fn char_limit_stop(&self, chars: usize) -> Option<StopReason> {
+        (self.max_chars_policy == LimitPolicy::Stop && self.at_char_limit(chars))
+            .then_some(StopReason::MaxChars)
+    }
+
+    /// With `--max-chars-policy truncate`, cut the session's text so that
+    /// after `before` characters of earlier text it stays within the limit
+    fn clip(&self, text: String, before: usize) -> String {
+        match self.max_chars {
+            Some(max) if self.max_chars_policy == LimitPolicy::Truncate => {
+                output::truncate(&text, max.saturating_sub(before)).to_string()
+            }
+            _ => text,
+        }
+    }
+
+    /// Whether the input device changed within the last `DEVICE_CHANGED_NOTICE`
+    fn device_changed(&self) -> bool {
+        self.shared
+            .device_changed
+            .last()
+            .is_some_and(|at| at.elapsed() < DEVICE_CHANGED_NOTICE)
+    }
+
+    /// Whether the backend dropped audio within the last `DROPPING_AUDIO_NOTICE`
+    fn dropping_audio(&self) -> bool {
+        self.shared
+            .audio_dropped
+            .last()
+            .is_some_and(|at| at.elapsed() < DROPPING_AUDIO_NOTICE)
+    }
+
     fn stop_listening(&mut self) {
         if let Some(ref mut recognizer) = self.recognizer {
             recognizer.stop();
         }
-        self.is_listening.store(false, Ordering::SeqCst);
+        self.shared.is_listening.store(false, Ordering::SeqCst);
+        self.shared.recording.flush();
     }
 
+    /// Start over with an empty transcription (also how a failed session is
+    /// retried); start_listening clears the error and the session's state
     fn restart(&mut self) -> Result<()> {
+        log::debug!("Restarting session");
         self.stop_listening();
-        self.transcription.lock().unwrap().clear();
+        self.paused = false;
+        self.shared.transcription.lock().unwrap().clear();
         self.start_time = Instant::now();
-        self.is_ready.store(false, Ordering::SeqCst);
+        self.auto_stop.reset();
+        self.stop_reason = None;
+        self.shared.is_ready.store(false, Ordering::SeqCst);
+        self.start_listening()
+    }
+}
 
-        let transcription = Arc::clone(&self.transcription);
-        let is_listening = Arc::clone(&self.is_listening);
-        let is_ready = Arc::clone(&self.is_ready);
+fn main() {
+    // Exit only once run() has returned, so the recognizer (and with it the
+    // microphone) is released by its destructor on every path
+    let code = match run() {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            1
+        }
+    };
+    std::process::exit(code);
+}
 
-        self.recognizer = Some(SpeechRecognizer::new(
-            transcription,
-            is_listening,
-            is_ready,
-        )?);
-        self.recognizer.as_mut().unwrap().start()?;
-        Ok(())
+/// Run claudio and return the process exit status
+fn run() -> Result<i32> {
+    let mut args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(2);
+        }
+    };
+
+    if args.version {
+        return print_version(&args);
+    }
+    if args.subcommand == Some(Subcommand::Locales) {
+        return list_locales(&args);
+    }
+    if args.subcommand == Some(Subcommand::Doctor) {
+        return doctor(&args);
+    }
+    if args.subcommand == Some(Subcommand::Once) {
+        // One utterance: headless, ending at the first pause in speech
+        args.no_ui = true;
+        args.silence_timeout.get_or_insert(ONCE_SILENCE_TIMEOUT);
     }
-}
 
-fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
-    let exec_command = args.iter().position(|a| a == "--").and_then(|pos| {
-        if pos + 1 < args.len() {
-            Some(args[pos + 1..].to_vec())
-        } else {
+    // Without a terminal (cron, CI, editor subprocesses) raw mode can't be
+    // enabled, so fall back to headless instead of failing cryptically
+    if !args.no_ui && !std::io::stdin().is_terminal() && !std::io::stderr().is_terminal() {
+        eprintln!("claudio: no terminal detected, running headless (--no-ui)");
+        args.no_ui = true;
+    }
+    if args.stream && !args.no_ui {
+        eprintln!("--stream needs headless mode (--no-ui)");
+        return Ok(2);
+    }
+    if args.start_paused && args.no_ui {
+        eprintln!("--start-paused needs the terminal UI to read the key");
+        return Ok(2);
+    }
+    if args.push_to_talk {
+        if args.no_ui {
+            eprintln!("--push-to-talk needs the terminal UI to read the key");
+            return Ok(2);
+        }
+        if !args.recognizer.engine.gates_audio() {
+            eprintln!(
+                "--push-to-talk isn't supported by the {} engine",
+                args.recognizer.engine.resolve()
+            );
+            return Ok(2);
+        }
+    }
+    match logging::init(args.verbose, args.log_file.as_deref(), args.no_ui) {
+        Ok(Some(path)) => eprintln!("claudio: logging to {}", path.display()),
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(2);
+        }
+    }
+    log::debug!("Starting with {:?}", args.recognizer);
+
+    let resumed_text = match Autosave::find_abandoned() {
+        // Recovered into this run, which autosaves it again
+        Some((path, text)) if args.resume => {
+            let _ = fs::remove_file(path);
+            Some(text)
+        }
+        Some((path, _)) => {
+            eprintln!(
+                "Found an unsaved session from a previous run ({}); use --resume to recover it.",
+                path.display()
+            );
             None
         }
-    });
+        None => None,
+    };
 
-    let mut app = App::new();
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(2);
+        }
+    };
+    // A command after `--` always wins over the configured default
+    if args.exec_command.is_none() {
+        args.exec_command = config.default_exec.clone().filter(|cmd| !cmd.is_empty());
+    }
+    if args.stream_exec && args.exec_command.is_none() {
+        eprintln!("--stream-exec requires a command after -- (or default_exec in the config)");
+        return Ok(2);
+    }
 
-    if let Err(e) = app.start_listening() {
+    // --self-check: the mock script says what the output should be
+    let expected_text = if args.self_check {
+        match claudio::speech::mock_expected_text() {
+            Some(text) if args.recognizer.engine.resolve() == Engine::Mock => Some(text),
+            _ => {
+                eprintln!(
+                    "--self-check needs --engine mock and expect: lines in CLAUDIO_MOCK_SCRIPT"
+                );
+                return Ok(2);
+            }
+        }
+    } else {
+        None
+    };
+
+    let keymap = match Keymap::from_config(&config.keys) {
+        Ok(keymap) => keymap,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(2);
+        }
+    };
+
+    let filter = match args.filter_words.as_deref().map(WordFilter::load) {
+        Some(Ok(filter)) => Some(filter),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            return Ok(2);
+        }
+        None => None,
+    };
+
+    let mut app = App::new(&args, keymap, VoiceCommands::from_config(&config.commands));
+    // A supervisor stopping claudio or the terminal closing still gets the
+    // transcription printed and the terminal restored
+    signal_hook::flag::register(signal_hook::consts::SIGTERM, Arc::clone(&app.terminated))?;
+    #[cfg(unix)]
+    signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(&app.terminated))?;
+    if args.filter_live {
+        app.live_filter = filter.clone();
+    }
+    if args.stream {
+        app.line_stream = Some(LineStream::new(args.line_ending, args.raw, filter.clone()));
+    }
+    app.messages = Messages::load(
+        args.ui_lang.as_deref().or(config.ui_lang.as_deref()),
+        &config.messages,
+    );
+    if let Some(path) = &args.save_audio {
+        if !args.recognizer.engine.records_audio() {
+            eprintln!(
+                "--save-audio isn't supported by the {} engine; no audio will be saved",
+                args.recognizer.engine.resolve()
+            );
+        } else if let Err(e) = app.shared.recording.create(path) {
+            eprintln!("{}", e);
+            return Ok(2);
+        }
+    }
+
+    // --start-paused: the microphone is only opened on the first key press
+    if app.waiting_to_start() {
+        log::debug!("Waiting for a key to start listening");
+    } else if let Err(e) = app.start_listening() {
+        log::error!("Failed to start speech recognition: {}", e);
         eprintln!("Failed to start speech recognition: {}", e);
         eprintln!("Make sure you have granted microphone and speech recognition permissions.");
-        std::process::exit(1);
+        return Ok(EXIT_RECOGNIZER_ERROR);
+    }
+
+    if args.stream_exec {
+        let cmd_args = args.exec_command.as_deref().unwrap_or_default();
+        if args.dry_run {
+            eprintln!("Would stream updates to: {}", exec::command_line(cmd_args));
+        } else {
+            match StreamExec::spawn(cmd_args, args.raw) {
+                Ok(stream) => app.stream = Some(stream),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return Ok(1);
+                }
+            }
+        }
     }
 
-    let final_text = run_app(&mut app)?;
+    let final_text = if args.no_ui {
+        run_headless(&mut app, resumed_text)?
+    } else {
+        run_app(&mut app, &args, resumed_text)?
+    };
+    let duration = app.start_time.elapsed();
+    log::debug!(
+        "Session finished with status {} ({} chars)",
+        app.exit_code,
+        final_text.chars().count()
+    );
+    let final_text = if args.no_trim {
+        final_text
+    } else {
+        output::normalize(&final_text)
+    };
+    let final_text = match &filter {
+        Some(filter) => filter.apply(&final_text).into_owned(),
+        None => final_text,
+    };
+    // --grammar: output the phrase that was said, or nothing
+    let final_text = if args.recognizer.grammar.is_empty() {
+        final_text
+    } else {
+        let phrase = grammar::find(&args.recognizer.grammar, &final_text);
+        log::debug!("Grammar match for {:?}: {:?}", final_text, phrase);
+        phrase.unwrap_or_default().to_string()
+    };
+    let final_text = match args.max_chars {
+        Some(max) => output::truncate(&final_text, max).to_string(),
+        None => final_text,
+    };
+    let final_text = output::wrap(final_text, args.prepend.as_deref(), args.append.as_deref());
+    let final_text = if args.raw {
+        final_text
+    } else {
+        output::sanitize(&final_text).into_owned()
+    };
+    let final_text = args.line_ending.apply(final_text);
+    let newline = (!args.no_newline).then_some(args.line_ending);
+
+    if let Some(expected) = &expected_text {
+        if app.exit_code == 0 && final_text != *expected {
+            eprintln!(
+                "Self-check failed\n  expected: {:?}\n  actual:   {:?}",
+                expected, final_text
+            );
+            return Ok(1);
+        }
+    }
+
+    // Release the microphone before handing the text on; the command may
+    // want to record too
+    app.recognizer = None;
+    app.stats.finalized();
+    if args.stats {
+        app.stats.print();
+    }
+
+    // A recognizer error keeps whatever was transcribed but fails the run
+    let error = app.shared.error.lock().unwrap().clone();
+    if let Some(error) = &error {
+        eprintln!("{}", error);
+    }
+
+    // A stop condition reports its own status in place of a plain success,
+    // or --timeout-exit-code (the pause ending `claudio once` is how it's
+    // meant to finish)
+    let success = match app.stop_reason {
+        Some(reason) if args.subcommand != Some(Subcommand::Once) => {
+            args.timeout_exit_code.unwrap_or(stop_exit_code(reason))
+        }
+        _ => 0,
+    };
+
+    // Sinks that get the text in addition; --webhook is sent before the
+    // text is printed or handed to the command
+    // Counted apart from the destinations: an extra failing doesn't mean
+    // the text went nowhere, and a command's status still wins over it
+    let sinks = Sinks::from_args(&args, duration);
+    let mut extras_failed = 0;
+    if app.exit_code == 0 && error.is_none() && !final_text.is_empty() {
+        extras_failed = sink::deliver(&sinks.extras, &final_text, args.dry_run);
+    }
+    let mut failed = 0;
+
+    // Streaming already delivered the updates; send the final text, then
+    // close its stdin and reap it
+    if let Some(mut stream) = app.stream.take() {
+        if app.exit_code == 0 {
+            stream.update(&final_text);
+            if !final_text.is_empty() {
+                failed += sink::deliver(&sinks.destinations, &final_text, false);
+            }
+        }
+        let code = stream.finish()?;
+        return Ok(if app.exit_code != 0 {
+            app.exit_code
+        } else if error.is_some() {
+            EXIT_RECOGNIZER_ERROR
+        } else if failed > 0 {
+            1
+        } else if code == 0 {
+            success
+        } else {
+            code
+        });
+    }
 
     if app.exit_code == 0 && !final_text.is_empty() {
-        if let Some(cmd_args) = exec_command {
-            let mut child = Command::new(&cmd_args[0])
-                .args(&cmd_args[1..])
-                .stdin(std::process::Stdio::piped())
-                .spawn()?;
-            if let Some(mut stdin) = child.stdin.take() {
-                stdin.write_all(final_text.as_bytes())?;
+        if args.dry_run {
+            // Show what would happen without running anything
+            eprintln!("Transcription:\n{}", final_text);
+            sink::deliver(&sinks.destinations, &final_text, true);
+            if let Some(cmd_args) = &args.exec_command {
+                if !args.stream_exec {
+                    eprintln!(
+                        "Would run: {}",
+                        exec::preview(cmd_args, &final_text, args.arg_template)
+                    );
+                }
             }
-            let status = child.wait()?;
-            std::process::exit(status.code().unwrap_or(1));
         } else {
-            // Print final transcription to stdout
-            println!("{}", final_text);
+            failed += sink::deliver(&sinks.destinations, &final_text, false);
+
+            // Don't hand a partial transcription from a failed session to the
+            // command. Its output is the only copy of the text unless another
+            // destination got it, so print it if the command doesn't run.
+            if let (Some(cmd_args), None) = (&args.exec_command, &error) {
+                let fallback = sinks.destinations.is_empty();
+                if args.confirm && !confirm_exec(cmd_args, &final_text, args.arg_template) {
+                    // Declined: print the text instead so it isn't lost
+                    if fallback {
+                        output::print(&final_text, newline);
+                    }
+                    return Ok(if failed > 0 { 1 } else { 0 });
+                }
+                match exec::run(cmd_args, &final_text, args.arg_template) {
+                    Ok(0) if failed > 0 => return Ok(1),
+                    Ok(0) => return Ok(success),
+                    Ok(code) => return Ok(code),
+                    Err(e) => {
+                        // Don't lose the dictation: print it so it can be recovered
+                        if fallback {
+                            output::print(&final_text, newline);
+                        }
+                        eprintln!("{}", e);
+                        return Ok(1);
+                    }
+                }
+            }
+
+            // Every destination failed: print it so it isn't lost
+            if failed > 0 && failed == sinks.destinations.len() {
+                output::print(&final_text, newline);
+            }
         }
     }
 
-    std::process::exit(app.exit_code);
+    // Ctrl+C discards the transcription unless asked to keep it; it's only
+    // printed, never handed to the command
+    if app.exit_code == EXIT_CANCELLED && args.keep_on_cancel && !final_text.is_empty() {
+        output::print(&final_text, newline);
+    }
+
+    if app.exit_code == 0 && error.is_some() {
+        return Ok(EXIT_RECOGNIZER_ERROR);
+    }
+    if app.exit_code == 0 && (failed > 0 || extras_failed > 0) {
+        return Ok(1);
+    }
+    Ok(if app.exit_code == 0 {
+        success
+    } else {
+        app.exit_code
+    })
 }
 
+/// `--confirm`: ask on the terminal whether to run the command. Without a
+/// terminal to ask on, the answer is no.
+fn confirm_exec(cmd_args: &[String], text: &str, arg_template: bool) -> bool {
+    let command = exec::preview(cmd_args, text, arg_template);
+    confirm::ask(text, &command).unwrap_or_else(|e| {
+        eprintln!("Can't ask for confirmation, not running the command: {}", e);
+        false
+    })
+}
+
+/// `--version`: print the version, the engines built in and the default one,
+/// and the optional cargo features enabled (or all of it as JSON with `--json`)
+fn print_version(args: &Args) -> Result<i32> {
+    let engines: Vec<&str> = Engine::available().into_iter().map(Engine::name).collect();
+    let default_engine = Engine::default().resolve().name();
+    let features: Vec<&str> = [
+        ("vosk", cfg!(feature = "vosk")),
+        ("whisper", cfg!(feature = "whisper")),
+        ("http", cfg!(feature = "http")),
+    ]
+    .into_iter()
+    .filter_map(|(name, enabled)| enabled.then_some(name))
+    .collect();
+
+    if args.json {
+        let info = serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "os": std::env::consts::OS,
+            "engines": engines,
+            "default_engine": default_engine,
+            "features": features,
+        });
+        println!("{}", info);
+    } else {
+        println!("claudio {}", env!("CARGO_PKG_VERSION"));
+        println!("engines: {}", engines.join(", "));
+        println!("default engine: {}", default_engine);
+        if !features.is_empty() {
+            println!("features: {}", features.join(", "));
+        }
+    }
+    Ok(0)
+}
+
+/// `claudio locales`: print the languages the selected engine supports, one
+/// per line (or as a JSON array with `--json`)
+fn list_locales(args: &Args) -> Result<i32> {
+    let languages = match claudio::speech::supported_languages(args.recognizer.engine) {
+        Ok(languages) => languages,
+        Err(e) => {
+            eprintln!("Failed to list languages: {}", e);
+            return Ok(1);
+        }
+    };
+    if args.json {
+        println!("{}", serde_json::to_string(&languages)?);
+    } else {
+        for language in languages {
+            println!("{}", language);
+        }
+    }
+    Ok(0)
+}
+
+/// `claudio doctor`: check that the selected engine can run, printing a
+/// PASS/FAIL line per check (or a JSON array with `--json`). Exits with 1 if
+/// any check failed.
+fn doctor(args: &Args) -> Result<i32> {
+    let checks = claudio::speech::diagnose(&args.recognizer);
+    if args.json {
+        let checks: Vec<_> = checks
+            .iter()
+            .map(|check| {
+                serde_json::json!({
+                    "check": check.name,
+                    "passed": check.passed,
+                    "detail": check.detail,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::Value::from(checks));
+    } else {
+        for check in &checks {
+            let result = if check.passed { "PASS" } else { "FAIL" };
+            println!("{} {:<14} {}", result, check.name, check.detail);
+        }
+    }
+    Ok(if checks.iter().all(|check| check.passed) {
+        0
+    } else {
+        1
+    })
+}
+
+/// Exit status after Ctrl+C (128 + SIGINT, as shells report it)
+const EXIT_CANCELLED: i32 = 130;
+
+/// Exit status when the recognizer failed to start or failed mid-session
+/// (whatever was transcribed is still output)
+const EXIT_RECOGNIZER_ERROR: i32 = 3;
+
+/// Exit status when a stop condition ended the recording, unless
+/// `--timeout-exit-code` gives one for all of them
+fn stop_exit_code(reason: StopReason) -> i32 {
+    match reason {
+        StopReason::Silence => 4,
+        StopReason::MaxDuration => 5,
+        StopReason::MaxChars => 6,
+    }
+}
+
+/// Pause that ends the utterance in `claudio once` (unless --silence-timeout)
+const ONCE_SILENCE_TIMEOUT: Duration = Duration::from_millis(1200);
+
 const MIN_LINES: usize = 1;
 const MAX_LINES: usize = 10;
 
-fn run_app(app: &mut App) -> Result<String> {
+/// Time between spinner frames
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long to wait for the first audio buffer before hinting at the mic
+const NO_AUDIO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long the notice about a changed input device stays up
+const DEVICE_CHANGED_NOTICE: Duration = Duration::from_secs(3);
+
+/// How long the warning about dropped audio stays up after the last drop
+const DROPPING_AUDIO_NOTICE: Duration = Duration::from_secs(3);
+
+/// How long before an auto-stop the spinner counts down instead
+const COUNTDOWN: Duration = Duration::from_secs(5);
+
+fn run_app(app: &mut App, args: &Args, resumed_text: Option<String>) -> Result<String> {
     let tick_rate = Duration::from_millis(33);
     let mut last_tick = Instant::now();
+    let mut was_ready = false;
+    let mut had_no_audio = false;
+    let mut redraw = true; // Draw even if the UI state is unchanged
 
     // termwiz uses /dev/tty on Unix, CONIN$/CONOUT$ on Windows - works with piped stdout
     let caps = Capabilities::new_from_env().map_err(|e| anyhow::anyhow!("{}", e))?;
@@ -185,62 +793,207 @@ fn run_app(app: &mut App) -> Result<String> {
 
     // Create inline terminal - starts with minimum height
     let mut term = InlineTerminal::new(terminal, MIN_LINES)?;
+    if args.no_cursor_blink {
+        term.set_cursor_shape(CursorShape::SteadyBar);
+    }
 
     // Raw mode for immediate keys, no alternate screen for inline rendering
     term.terminal()
         .set_raw_mode()
         .map_err(|e| anyhow::anyhow!("{}", e))?;
 
+    // Waiting for a key ends early when the recognizer finishes a session
+    let waker = term.terminal().waker();
+    app.shared.completion.on_signal(move || {
+        let _ = waker.wake();
+    });
+
     // Initialize UI
     let mut ui = Ui::new();
+    ui.set_keymap(&app.keymap);
+    ui.messages = app.messages.clone();
+    ui.color_mode = ColorMode::detect();
+    ui.animation = args.animation;
+    ui.word_wrap = !args.no_word_wrap;
+    ui.background = args.background.unwrap_or_else(Background::detect);
+    ui.glyphs = if args.ascii {
+        &Glyphs::ASCII
+    } else {
+        Glyphs::detect()
+    };
+    if let Some(text) = resumed_text {
+        ui.set_frozen_text(text);
+        ui.ensure_trailing_space();
+    }
+
+    let talk_hint = app
+        .keymap
+        .chord(Action::Talk)
+        .map(|key| ui.messages.hold_to_talk(&key.to_string()));
+    let start_hint = ui.messages.press_to_start.clone();
+    let mut autosave = Autosave::new();
+    let mut debounce = Debounce::new(args.debounce);
 
     loop {
         let elapsed_ms = app.start_time.elapsed().as_millis() as f32;
 
         // Update spinner frame
-        if last_tick.elapsed() >= Duration::from_millis(100) {
+        if last_tick.elapsed() >= SPINNER_INTERVAL {
             ui.tick();
             last_tick = Instant::now();
         }
 
         // Update UI state from app
-        let is_ready = app.is_ready.load(Ordering::SeqCst);
-        let is_listening = app.is_listening.load(Ordering::SeqCst);
+        let is_ready = app.shared.is_ready.load(Ordering::SeqCst);
+        let is_listening = app.shared.is_listening.load(Ordering::SeqCst);
+
+        // Signal once when it's safe to start talking
+        if is_ready && !was_ready && args.beep {
+            term.bell()?;
+        }
+        was_ready = is_ready;
+        app.stats.check_startup(&app.shared, is_ready);
 
-        ui.spinner_state = if !is_ready {
+        // --push-to-talk: pass audio on only while the key is held
+        let talking = match &app.talk {
+            Some(talk) => {
+                let held = talk.is_held();
+                if app.shared.muted.swap(!held, Ordering::SeqCst) == held {
+                    log::debug!("Talk key {}", if held { "held" } else { "released" });
+                }
+                held
+            }
+            None => true,
+        };
+
+        ui.spinner_state = if app.shared.is_reconnecting.load(Ordering::SeqCst) {
+            SpinnerState::Reconnecting
+        } else if app.shared.is_loading_model.load(Ordering::SeqCst) {
+            SpinnerState::LoadingModel
+        } else if app.waiting_to_start() {
+            SpinnerState::Idle
+        } else if !is_ready {
             SpinnerState::Loading
-        } else if is_listening {
+        } else if is_listening && talking {
             SpinnerState::Listening
         } else {
             SpinnerState::Idle
         };
 
-        ui.show_placeholder = is_ready && is_listening && ui.is_empty();
-        ui.show_controls = is_ready;
+        ui.show_placeholder = !args.no_placeholder && is_ready && is_listening && ui.is_empty();
+        ui.hint = if app.waiting_to_start() {
+            Some(start_hint.clone())
+        } else {
+            talk_hint.clone().filter(|_| is_ready && !talking)
+        };
+        ui.error = app.shared.error.lock().unwrap().clone();
+        ui.engine_status = if app.show_status {
+            app.engine_status()
+        } else {
+            Vec::new()
+        };
+
+        // Turn a silent hang on the spinner into an actionable hint
+        let no_audio = ui.mode == Mode::Listening && app.no_audio();
+        if no_audio && !had_no_audio {
+            log::warn!("No audio received after {:?}", NO_AUDIO_TIMEOUT);
+        }
+        had_no_audio = no_audio;
+        let at_limit =
+            ui.mode == Mode::Listening && app.at_char_limit(ui.full_text().chars().count());
+        ui.warning = if no_audio {
+            Some(ui.messages.no_audio.clone())
+        } else if ui.mode == Mode::Listening && app.device_changed() {
+            Some(ui.messages.device_changed.clone())
+        } else if ui.mode == Mode::Listening && app.dropping_audio() {
+            Some(ui.messages.dropping_audio.clone())
+        } else if at_limit {
+            Some(ui.messages.limit_reached.clone())
+        } else {
+            None
+        };
+        // --minimal leaves the last row to errors and warnings
+        let show_bar = !args.minimal && (is_ready || app.waiting_to_start());
+        ui.show_controls = show_bar || ui.error.is_some() || ui.warning.is_some();
 
         // Update speech text - diff with previous determines animation
-        let speech_text = app.transcription.lock().unwrap().clone();
+        let text_changed = app.shared.text_changed.last();
+        let speech_text = app.clip(app.transcription(), ui.confirmed_chars());
+        // --debounce-ms: hold back rewrites, but never the text being output
+        let speech_text = if app.should_quit {
+            speech_text
+        } else {
+            debounce.update(speech_text)
+        };
         ui.set_text(&speech_text, elapsed_ms);
 
-        // Check for terminal width resize (debounced)
-        term.check_for_resize()?;
+        // --continuous: keep the text of a session the recognizer ended and
+        // listen again, as if paused and resumed
+        if app.continuous && ui.mode == Mode::Listening && !app.should_quit && app.session_ended() {
+            log::debug!("Session ended, listening again");
+            let speech_text = app.clip(app.take_transcription(), ui.confirmed_chars());
+            ui.set_text(&speech_text, elapsed_ms);
+            ui.freeze_text();
+            app.resume_listening()?;
+        }
+
+        // Finish automatically on --silence-timeout / --max-duration /
+        // --max-chars (not while editing)
+        if ui.mode == Mode::Listening && !app.should_quit {
+            if let Some(reason) = app
+                .auto_stop
+                .should_stop(&speech_text, is_ready && !app.paused)
+                .or_else(|| app.char_limit_stop(ui.full_text().chars().count()))
+            {
+                log::info!("Auto-stop condition met: {:?}", reason);
+                app.stats.finish_requested();
+                app.stop_listening();
+                app.should_quit = true;
+                app.exit_code = 0;
+                app.stop_reason = Some(reason);
+            }
+        }
+
+        // Count down the last seconds before an auto-stop
+        ui.countdown = app
+            .auto_stop
+            .remaining()
+            .filter(|left| {
+                ui.mode == Mode::Listening && !app.paused && !app.should_quit && *left < COUNTDOWN
+            })
+            .map(|left| left.as_secs() + 1);
+
+        // Apply any settled terminal width change (debounced)
+        if term.check_for_resize()? {
+            redraw = true;
+        }
 
         // Skip rendering while resize is settling
         if !term.is_resizing() {
             // Check if we need to resize the surface for wrapping
             let (width, current_height) = term.surface().dimensions();
+            ui.set_screen_rows(term.screen_rows());
             let needed_lines = ui.lines_needed(width).min(MAX_LINES);
             if needed_lines != current_height {
                 term.resize_height(needed_lines)?;
+                redraw = true;
             }
 
-            // Render UI to surface
-            ui.render(term.surface(), elapsed_ms);
-            let cursor_pos = ui.cursor_screen_position(width);
-            term.render_with_cursor(cursor_pos)?;
+            // Render UI to surface, only when something on screen changed
+            if redraw || ui.needs_render(elapsed_ms) {
+                ui.render(term.surface(), elapsed_ms);
+                let cursor_pos = ui.cursor_screen_position(width, needed_lines);
+                term.render_with_cursor(cursor_pos)?;
+                app.stats.shown(text_changed);
+                app.stats.frame();
+                redraw = false;
+            }
         }
 
         if app.should_quit {
+            // Normal exit - nothing to recover
+            autosave.discard();
+
             // Clean up the UI
             term.cleanup()?;
             term.terminal()
@@ -251,41 +1004,232 @@ fn run_app(app: &mut App) -> Result<String> {
             return Ok(ui.full_text().to_string());
         }
 
-        // Poll input
+        let full_text = ui.full_text();
+        autosave.tick(&full_text);
+        if let Some(stream) = &mut app.stream {
+            stream.update(&full_text);
+        }
+
+        // Poll input. The end of a session wakes the poll, but transcription
+        // updates aren't signalled, so check for them often while listening;
+        // otherwise only the spinner can move
+        let timeout = if is_listening || ui.is_animating(elapsed_ms) {
+            tick_rate
+        } else {
+            SPINNER_INTERVAL
+        };
         if let Some(event) = term
             .terminal()
-            .poll_input(Some(tick_rate))
+            .poll_input(Some(timeout))
             .map_err(|e| anyhow::anyhow!("{}", e))?
         {
-            handle_input(app, &mut ui, event)?;
+            redraw = true;
+            match event {
+                // Delivered on SIGWINCH; text is reflowed once the resize settles
+                InputEvent::Resized { cols, rows } => term.notify_resize(cols, rows),
+                // The session ended; the next pass picks it up
+                InputEvent::Wake => {}
+                event => handle_input(app, &mut ui, &mut term, event)?,
+            }
+        }
+
+        // Finish on SIGTERM/SIGHUP; the next pass picks up the final result
+        if app.terminated.load(Ordering::SeqCst) && !app.should_quit {
+            log::info!("Terminated by signal, finishing");
+            app.stats.finish_requested();
+            app.stop_listening();
+            app.should_quit = true;
+            app.exit_code = 0;
+            redraw = true;
         }
     }
 }
 
-fn handle_input(app: &mut App, ui: &mut Ui, event: InputEvent) -> Result<()> {
+/// Run without any terminal UI until the recognizer finishes, a
+/// --silence-timeout/--max-duration/--max-chars stop condition is met, or
+/// SIGINT, SIGTERM or SIGHUP.
+fn run_headless(app: &mut App, resumed_text: Option<String>) -> Result<String> {
+    let tick_rate = Duration::from_millis(33);
+
+    // Without raw mode Ctrl+C arrives as SIGINT - treat it as "finish"
+    let interrupted = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGINT, Arc::clone(&interrupted))?;
+    let mut warned_no_audio = false;
+    let mut device_changed_at = None;
+    let mut warned_dropping = false;
+    let mut warned_limit = false;
+
+    // Recovered text is followed by new speech, so keep the two apart
+    let mut resumed_text = resumed_text.unwrap_or_default();
+    if !resumed_text.is_empty() && !resumed_text.ends_with(char::is_whitespace) {
+        resumed_text.push(' ');
+    }
+    if let Some(line_stream) = &mut app.line_stream {
+        if !resumed_text.is_empty() {
+            line_stream.write_line(resumed_text.trim());
+        }
+    }
+
+    loop {
+        let is_ready = app.shared.is_ready.load(Ordering::SeqCst);
+        app.stats.check_startup(&app.shared, is_ready);
+        let text_changed = app.shared.text_changed.last();
+        let speech_text = app.clip(app.transcription(), resumed_text.chars().count());
+        if let Some(stream) = &mut app.stream {
+            stream.update(&format!("{}{}", resumed_text, speech_text));
+        }
+        app.stats.shown(text_changed);
+
+        if !warned_no_audio && app.no_audio() {
+            log::warn!("No audio received after {:?}", NO_AUDIO_TIMEOUT);
+            eprintln!("claudio: {}", app.messages.no_audio);
+            warned_no_audio = true;
+        }
+        let changed_at = app.shared.device_changed.last();
+        if changed_at != device_changed_at {
+            eprintln!("claudio: {}", app.messages.device_changed);
+            device_changed_at = changed_at;
+        }
+        // Once per stretch of dropped audio
+        let dropping = app.dropping_audio();
+        if dropping && !warned_dropping {
+            eprintln!("claudio: {}", app.messages.dropping_audio);
+        }
+        warned_dropping = dropping;
+        let chars = resumed_text.chars().count() + speech_text.chars().count();
+        if !warned_limit && app.at_char_limit(chars) {
+            eprintln!("claudio: {}", app.messages.limit_reached);
+            warned_limit = true;
+        }
+
+        // --continuous: keep the text of a session the recognizer ended and
+        // listen again
+        if app.continuous && app.session_ended() {
+            log::debug!("Session ended, listening again");
+            let speech_text = app.clip(app.take_transcription(), resumed_text.chars().count());
+            if let Some(line_stream) = &mut app.line_stream {
+                line_stream.update(&speech_text);
+                line_stream.new_session();
+            }
+            if !speech_text.is_empty() {
+                resumed_text.push_str(&speech_text);
+                resumed_text.push(' ');
+            }
+            app.resume_listening()?;
+            continue;
+        }
+
+        // The session is only over with a final result unless --continuous.
+        // Going by the completion signal, not the flags: a backend may clear
+        // is_ready as its session completes, which would never count as
+        // finished while the wait below returned at once every pass.
+        let finished = !app.continuous && app.shared.completion.is_done();
+        let failed = app.shared.error.lock().unwrap().is_some();
+        // --stream: a reader that went away ends recording like Ctrl+C
+        let committed = app.committed_transcription();
+        let reader_gone = app
+            .line_stream
+            .as_mut()
+            .is_some_and(|line_stream| !line_stream.update(&committed));
+
+        let terminated = app.terminated.load(Ordering::SeqCst);
+        if interrupted.load(Ordering::SeqCst) || terminated || reader_gone || finished || failed {
+            app.stats.finish_requested();
+            break;
+        }
+        let reason = app
+            .auto_stop
+            .should_stop(&speech_text, is_ready)
+            .or_else(|| app.char_limit_stop(chars));
+        if let Some(reason) = reason {
+            log::info!("Auto-stop condition met: {:?}", reason);
+            app.stats.finish_requested();
+            app.stop_reason = Some(reason);
+            break;
+        }
+
+        // Wakes early when the recognizer delivers its final result
+        app.shared.completion.wait_timeout(tick_rate);
+    }
+
+    // Stopping may deliver a final result, so read the transcription afterwards
+    app.stop_listening();
+    let speech_text = app.clip(app.transcription(), resumed_text.chars().count());
+    if let Some(line_stream) = &mut app.line_stream {
+        line_stream.update(&speech_text);
+    }
+    Ok(format!("{}{}", resumed_text, speech_text))
+}
+
+/// Run the external editor with the terminal handed over to it
+fn run_editor(term: &mut InlineTerminal<SystemTerminal>, text: &str) -> Result<String> {
+    term.suspend()?;
+    let edited = editor::open(text);
+    term.resume()?;
+    edited
+}
+
+fn handle_input(
+    app: &mut App,
+    ui: &mut Ui,
+    term: &mut InlineTerminal<SystemTerminal>,
+    event: InputEvent,
+) -> Result<()> {
     let InputEvent::Key(key) = event else {
         return Ok(());
     };
 
     match ui.mode {
-        Mode::Listening => handle_listening_input(app, ui, key),
-        Mode::Editing => handle_editing_input(app, ui, key),
+        Mode::Listening => handle_listening_input(app, ui, term, key),
+        Mode::Editing => handle_editing_input(app, ui, term, key),
     }
 }
 
-fn handle_listening_input(app: &mut App, ui: &mut Ui, key: termwiz::input::KeyEvent) -> Result<()> {
-    match (key.key, key.modifiers) {
-        (KeyCode::Enter, Modifiers::NONE) => {
+fn handle_listening_input(
+    app: &mut App,
+    ui: &mut Ui,
+    term: &mut InlineTerminal<SystemTerminal>,
+    key: termwiz::input::KeyEvent,
+) -> Result<()> {
+    let action = app.keymap.action(&key);
+
+    // --start-paused: any key starts listening, except those that end the
+    // session, edit the text or toggle the status (a talk key press also counts as holding it)
+    if app.waiting_to_start()
+        && !matches!(
+            action,
+            Some(
+                Action::Finish
+                    | Action::Cancel
+                    | Action::Edit
+                    | Action::ExternalEditor
+                    | Action::Status
+            )
+        )
+    {
+        app.resume_listening()?;
+        if action != Some(Action::Talk) {
+            return Ok(());
+        }
+    }
+
+    let Some(action) = action else {
+        return Ok(());
+    };
+
+    match action {
+        Action::Finish => {
+            app.stats.finish_requested();
             app.stop_listening();
             app.should_quit = true;
             app.exit_code = 0;
         }
-        (KeyCode::Char('c'), Modifiers::CTRL) => {
+        Action::Cancel => {
             app.stop_listening();
             app.should_quit = true;
-            app.exit_code = 130;
+            app.exit_code = EXIT_CANCELLED;
         }
-        (KeyCode::Char('d'), Modifiers::CTRL) => {
+        Action::Restart => {
             ui.reset(); // Clear frozen state
             if let Err(e) = app.restart() {
                 eprintln!("Failed to restart: {}", e);
@@ -293,17 +1237,31 @@ fn handle_listening_input(app: &mut App, ui: &mut Ui, key: termwiz::input::KeyEv
                 app.exit_code = 1;
             }
         }
-        (KeyCode::Char('e'), Modifiers::CTRL) => {
-            // Enter editing mode
-            app.edit_original = ui.full_text().to_string();
-            app.stop_listening(); // Pause speech recognition while editing
+        Action::ClearLive => {
+            // The recognizer reports its whole session's text, so start a
+            // new session; stopping first keeps a late final result of the
+            // old one out of it. While paused there's nothing live to clear.
+            if !app.paused {
+                app.stop_listening();
+                ui.clear_live();
+                app.resume_listening()?;
+            }
+        }
+        Action::Edit => {
+            // Enter editing mode (UI snapshots the text for cancel), with
+            // speech recognition paused while editing
+            app.stop_listening();
+            // Stopping may deliver a final result, and --debounce-ms may be
+            // holding back a rewrite; edit the latest text
+            let speech_text = app.clip(app.take_transcription(), ui.confirmed_chars());
+            ui.set_text(&speech_text, app.start_time.elapsed().as_millis() as f32);
             ui.start_editing();
         }
-        (KeyCode::Char('E'), Modifiers::CTRL | Modifiers::SHIFT) => {
+        Action::ExternalEditor => {
             // Open $EDITOR directly (hidden shortcut)
             app.stop_listening();
             let text = ui.full_text().to_string();
-            match open_editor(&text) {
+            match run_editor(term, &text) {
                 Ok(edited) => {
                     ui.set_frozen_text(edited);
                     app.should_quit = true;
@@ -316,33 +1274,57 @@ fn handle_listening_input(app: &mut App, ui: &mut Ui, key: termwiz::input::KeyEv
                 }
             }
         }
-        _ => {}
+        Action::Talk => {
+            if let Some(talk) = &mut app.talk {
+                talk.press();
+            }
+        }
+        Action::Status => app.show_status = !app.show_status,
+        Action::Pause => {
+            if app.paused {
+                app.resume_listening()?;
+            } else {
+                app.stop_listening();
+                // Stopping may deliver a final result; fold it in before the
+                // next session starts from an empty transcription
+                let speech_text = app.take_transcription();
+                ui.set_text(&speech_text, app.start_time.elapsed().as_millis() as f32);
+                ui.freeze_text();
+                app.paused = true;
+            }
+        }
     }
     Ok(())
 }
 
-fn handle_editing_input(app: &mut App, ui: &mut Ui, key: termwiz::input::KeyEvent) -> Result<()> {
+fn handle_editing_input(
+    app: &mut App,
+    ui: &mut Ui,
+    term: &mut InlineTerminal<SystemTerminal>,
+    key: termwiz::input::KeyEvent,
+) -> Result<()> {
     match (key.key, key.modifiers) {
+        // Submit the edited text
+        (KeyCode::Enter, Modifiers::NONE) => {
+            app.stats.finish_requested();
+            app.should_quit = true;
+            app.exit_code = 0;
+        }
         // Confirm edit
         (KeyCode::Char('s'), Modifiers::CTRL) => {
-            // Finish editing and freeze the text (UI manages the buffers)
+            // Finish editing and freeze the text (UI manages the buffers and
+            // the space before new speech)
             ui.finish_editing_with_freeze();
-            // Ensure trailing space for separation from new speech
-            ui.ensure_trailing_space();
-            // Clear the live transcription buffer for new speech
-            app.transcription.lock().unwrap().clear();
-            // Resume listening
-            app.start_listening()?;
+            app.resume_listening()?;
         }
         // Escalate to $EDITOR
         (KeyCode::Char('e'), Modifiers::CTRL) => {
             let text = ui.full_text().to_string();
-            match open_editor(&text) {
+            match run_editor(term, &text) {
                 Ok(edited) => {
                     ui.set_frozen_text(edited);
                     ui.ensure_trailing_space();
-                    app.transcription.lock().unwrap().clear();
-                    app.start_listening()?;
+                    app.resume_listening()?;
                 }
                 Err(e) => {
                     eprintln!("Editor error: {}", e);
@@ -352,9 +1334,8 @@ fn handle_editing_input(app: &mut App, ui: &mut Ui, key: termwiz::input::KeyEven
         }
         // Discard edits
         (KeyCode::Char('d'), Modifiers::CTRL) | (KeyCode::Escape, Modifiers::NONE) => {
-            ui.cancel_editing(&app.edit_original);
-            // Resume listening
-            app.start_listening()?;
+            ui.cancel_editing();
+            app.resume_listening()?;
         }
         // Navigation
         (KeyCode::LeftArrow, Modifiers::NONE) => ui.cursor_left(),
@@ -364,6 +1345,8 @@ fn handle_editing_input(app: &mut App, ui: &mut Ui, key: termwiz::input::KeyEven
         // Editing
         (KeyCode::Backspace, Modifiers::NONE) => ui.delete_back(),
         (KeyCode::Delete, Modifiers::NONE) => ui.delete_forward(),
+        (KeyCode::Char('u'), Modifiers::CTRL) => ui.delete_to_line_start(),
+        (KeyCode::Char('k'), Modifiers::CTRL) => ui.delete_to_line_end(),
         (KeyCode::Char(ch), Modifiers::NONE | Modifiers::SHIFT) => ui.insert_char(ch),
         _ => {}
     }
@@ -0,0 +1,324 @@
+//! Translated UI strings
+//!
+//! The language is taken from `--ui-lang`, then `ui_lang` in the config,
+//! then the system locale (`LC_ALL`, `LC_MESSAGES`, `LANG`), and falls back
+//! to English. Single strings can be overridden in the config's `[messages]`
+//! table.
+
+use std::env;
+
+use crate::config::MessageOverrides;
+
+/// A controls bar label, with the abbreviation used when space is short
+#[derive(Clone)]
+pub struct Label {
+    pub full: String,
+    pub short: String,
+}
+
+impl Label {
+    fn new((full, short): (&str, &str)) -> Self {
+        Self {
+            full: full.to_string(),
+            short: short.to_string(),
+        }
+    }
+
+    /// Replace the label; the abbreviation is its first three characters
+    fn set(&mut self, full: &str) {
+        self.full = full.to_string();
+        self.short = full.chars().take(3).collect();
+    }
+}
+
+/// Strings shown by the terminal UI
+#[derive(Clone)]
+pub struct Messages {
+    /// Placeholder while listening, before anything is recognized
+    pub speak_now: String,
+    /// Placeholder while `--push-to-talk`'s key is up; `{}` is the key
+    pub hold_to_talk: String,
+    /// Placeholder before recording starts with `--start-paused`
+    pub press_to_start: String,
+    /// Shown while the recognizer loads its model
+    pub loading_model: String,
+    /// Shown while the recognizer retries starting up
+    pub reconnecting: String,
+    /// Warning when the microphone delivers nothing
+    pub no_audio: String,
+    /// Notice when the input device changes mid-session
+    pub device_changed: String,
+    /// Warning while captured audio is dropped because recognition can't
+    /// keep up
+    pub dropping_audio: String,
+    /// Notice once the transcription reaches `--max-chars`
+    pub limit_reached: String,
+    /// Where recognition runs, in the `--show-status` segment
+    pub on_device: String,
+    pub server: String,
+    /// Scroll indicator for one hidden line
+    pub more_line: String,
+    /// Scroll indicator for several hidden lines; `{}` is the count
+    pub more_lines: String,
+    // Controls bar, recording
+    pub submit: Label,
+    pub edit: Label,
+    pub discard: Label,
+    pub clear: Label,
+    pub pause: Label,
+    pub cancel: Label,
+    // Controls bar, editing
+    pub save: Label,
+    pub editor: Label,
+    pub revert: Label,
+    pub move_cursor: Label,
+}
+
+/// A built-in translation
+struct Catalog {
+    lang: &'static str,
+    speak_now: &'static str,
+    hold_to_talk: &'static str,
+    press_to_start: &'static str,
+    loading_model: &'static str,
+    reconnecting: &'static str,
+    no_audio: &'static str,
+    device_changed: &'static str,
+    dropping_audio: &'static str,
+    limit_reached: &'static str,
+    on_device: &'static str,
+    server: &'static str,
+    more_line: &'static str,
+    more_lines: &'static str,
+    submit: (&'static str, &'static str),
+    edit: (&'static str, &'static str),
+    discard: (&'static str, &'static str),
+    clear: (&'static str, &'static str),
+    pause: (&'static str, &'static str),
+    cancel: (&'static str, &'static str),
+    save: (&'static str, &'static str),
+    editor: (&'static str, &'static str),
+    revert: (&'static str, &'static str),
+    move_cursor: (&'static str, &'static str),
+}
+
+const CATALOGS: &[Catalog] = &[
+    Catalog {
+        lang: "en",
+        speak_now: "Speak now...",
+        hold_to_talk: "Hold {} to talk",
+        press_to_start: "Press any key to start",
+        loading_model: "Loading model...",
+        reconnecting: "Reconnecting...",
+        no_audio: "No audio detected — check your microphone",
+        device_changed: "Audio device changed, reconnecting...",
+        dropping_audio: "Recognizer falling behind, dropping audio",
+        limit_reached: "Character limit reached",
+        on_device: "on-device",
+        server: "server",
+        more_line: "1 more line",
+        more_lines: "{} more lines",
+        submit: ("submit", "sub"),
+        edit: ("edit", "edt"),
+        discard: ("discard", "dis"),
+        clear: ("clear", "clr"),
+        pause: ("pause", "pau"),
+        cancel: ("cancel", "esc"),
+        save: ("save", "sav"),
+        editor: ("editor", "edt"),
+        revert: ("discard", "dis"),
+        move_cursor: ("move", "mv"),
+    },
+    Catalog {
+        lang: "de",
+        speak_now: "Jetzt sprechen...",
+        hold_to_talk: "Zum Sprechen {} gedrückt halten",
+        press_to_start: "Zum Starten eine Taste drücken",
+        loading_model: "Lade Modell...",
+        reconnecting: "Verbinde erneut...",
+        no_audio: "Kein Ton erkannt — Mikrofon prüfen",
+        device_changed: "Audiogerät gewechselt, verbinde erneut...",
+        dropping_audio: "Erkennung kommt nicht hinterher, Audio wird verworfen",
+        limit_reached: "Zeichenlimit erreicht",
+        on_device: "lokal",
+        server: "Server",
+        more_line: "1 weitere Zeile",
+        more_lines: "{} weitere Zeilen",
+        submit: ("senden", "snd"),
+        edit: ("bearbeiten", "bea"),
+        discard: ("verwerfen", "vrw"),
+        clear: ("leeren", "lee"),
+        pause: ("pause", "pau"),
+        cancel: ("abbrechen", "abb"),
+        save: ("speichern", "spe"),
+        editor: ("Editor", "edt"),
+        revert: ("verwerfen", "vrw"),
+        move_cursor: ("bewegen", "bew"),
+    },
+    Catalog {
+        lang: "es",
+        speak_now: "Habla ahora...",
+        hold_to_talk: "Mantén {} pulsado para hablar",
+        press_to_start: "Pulsa una tecla para empezar",
+        loading_model: "Cargando modelo...",
+        reconnecting: "Reconectando...",
+        no_audio: "No se detecta audio — revisa el micrófono",
+        device_changed: "Dispositivo de audio cambiado, reconectando...",
+        dropping_audio: "El reconocimiento no da abasto, se descarta audio",
+        limit_reached: "Límite de caracteres alcanzado",
+        on_device: "local",
+        server: "servidor",
+        more_line: "1 línea más",
+        more_lines: "{} líneas más",
+        submit: ("enviar", "env"),
+        edit: ("editar", "edi"),
+        discard: ("descartar", "des"),
+        clear: ("borrar", "bor"),
+        pause: ("pausa", "pau"),
+        cancel: ("cancelar", "can"),
+        save: ("guardar", "gua"),
+        editor: ("editor", "edt"),
+        revert: ("descartar", "des"),
+        move_cursor: ("mover", "mov"),
+    },
+    Catalog {
+        lang: "fr",
+        speak_now: "Parlez maintenant...",
+        hold_to_talk: "Maintenez {} pour parler",
+        press_to_start: "Appuyez sur une touche pour commencer",
+        loading_model: "Chargement du modèle...",
+        reconnecting: "Reconnexion...",
+        no_audio: "Aucun son détecté — vérifiez le micro",
+        device_changed: "Périphérique audio changé, reconnexion...",
+        dropping_audio: "La reconnaissance prend du retard, audio ignoré",
+        limit_reached: "Limite de caractères atteinte",
+        on_device: "local",
+        server: "serveur",
+        more_line: "1 ligne de plus",
+        more_lines: "{} lignes de plus",
+        submit: ("valider", "val"),
+        edit: ("modifier", "mod"),
+        discard: ("effacer", "eff"),
+        clear: ("vider", "vid"),
+        pause: ("pause", "pau"),
+        cancel: ("annuler", "ann"),
+        save: ("enregistrer", "enr"),
+        editor: ("éditeur", "édi"),
+        revert: ("annuler", "ann"),
+        move_cursor: ("déplacer", "dép"),
+    },
+];
+
+impl Messages {
+    /// Strings for a language tag such as "de-DE" or "fr_FR.UTF-8", English
+    /// if there's no translation
+    fn for_lang(tag: &str) -> Self {
+        let lang = tag
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or(tag)
+            .to_lowercase();
+        let catalog = CATALOGS
+            .iter()
+            .find(|catalog| catalog.lang == lang)
+            .unwrap_or(&CATALOGS[0]);
+        Self::from_catalog(catalog)
+    }
+
+    /// Strings for `ui_lang`, or the system locale when it's unset
+    pub fn load(ui_lang: Option<&str>, overrides: &MessageOverrides) -> Self {
+        let mut messages = match ui_lang {
+            Some(tag) => Self::for_lang(tag),
+            None => ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .find_map(|var| env::var(var).ok().filter(|v| !v.is_empty()))
+                .map(|tag| Self::for_lang(&tag))
+                .unwrap_or_default(),
+        };
+        messages.apply(overrides);
+        messages
+    }
+
+    fn from_catalog(catalog: &Catalog) -> Self {
+        Self {
+            speak_now: catalog.speak_now.to_string(),
+            hold_to_talk: catalog.hold_to_talk.to_string(),
+            press_to_start: catalog.press_to_start.to_string(),
+            loading_model: catalog.loading_model.to_string(),
+            reconnecting: catalog.reconnecting.to_string(),
+            no_audio: catalog.no_audio.to_string(),
+            device_changed: catalog.device_changed.to_string(),
+            dropping_audio: catalog.dropping_audio.to_string(),
+            limit_reached: catalog.limit_reached.to_string(),
+            on_device: catalog.on_device.to_string(),
+            server: catalog.server.to_string(),
+            more_line: catalog.more_line.to_string(),
+            more_lines: catalog.more_lines.to_string(),
+            submit: Label::new(catalog.submit),
+            edit: Label::new(catalog.edit),
+            discard: Label::new(catalog.discard),
+            clear: Label::new(catalog.clear),
+            pause: Label::new(catalog.pause),
+            cancel: Label::new(catalog.cancel),
+            save: Label::new(catalog.save),
+            editor: Label::new(catalog.editor),
+            revert: Label::new(catalog.revert),
+            move_cursor: Label::new(catalog.move_cursor),
+        }
+    }
+
+    fn apply(&mut self, overrides: &MessageOverrides) {
+        let strings = [
+            (&overrides.speak_now, &mut self.speak_now),
+            (&overrides.hold_to_talk, &mut self.hold_to_talk),
+            (&overrides.press_to_start, &mut self.press_to_start),
+            (&overrides.loading_model, &mut self.loading_model),
+            (&overrides.reconnecting, &mut self.reconnecting),
+            (&overrides.no_audio, &mut self.no_audio),
+            (&overrides.device_changed, &mut self.device_changed),
+            (&overrides.dropping_audio, &mut self.dropping_audio),
+            (&overrides.limit_reached, &mut self.limit_reached),
+            (&overrides.on_device, &mut self.on_device),
+            (&overrides.server, &mut self.server),
+        ];
+        for (value, target) in strings {
+            if let Some(value) = value {
+                target.clone_from(value);
+            }
+        }
+
+        let labels = [
+            (&overrides.submit, &mut self.submit),
+            (&overrides.edit, &mut self.edit),
+            (&overrides.discard, &mut self.discard),
+            (&overrides.clear, &mut self.clear),
+            (&overrides.pause, &mut self.pause),
+            (&overrides.cancel, &mut self.cancel),
+        ];
+        for (value, label) in labels {
+            if let Some(value) = value {
+                label.set(value);
+            }
+        }
+    }
+
+    /// The `--push-to-talk` placeholder for holding `key`
+    pub fn hold_to_talk(&self, key: &str) -> String {
+        self.hold_to_talk.replace("{}", key)
+    }
+
+    /// The scroll indicator text for `count` hidden lines
+    pub fn more(&self, count: usize) -> String {
+        if count == 1 {
+            self.more_line.clone()
+        } else {
+            self.more_lines.replace("{}", &count.to_string())
+        }
+    }
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Self::from_catalog(&CATALOGS[0])
+    }
+}
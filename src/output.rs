@@ -0,0 +1,187 @@
+//! Cleanup applied to the final transcription before it is printed or
+//! handed to a command (the live display is left untouched)
+
+use std::borrow::Cow;
+use std::io::{self, BufWriter, ErrorKind, Stdout, Write};
+use std::str::FromStr;
+
+use crate::filter::WordFilter;
+
+/// Line break used in the output (`--line-ending`)
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+    /// CRLF on Windows, LF elsewhere
+    Platform,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Platform if cfg!(target_os = "windows") => "\r\n",
+            LineEnding::Platform => "\n",
+        }
+    }
+
+    /// Use this line ending for the line breaks within `text`
+    pub fn apply(self, text: String) -> String {
+        match self.as_str() {
+            "\n" => text,
+            ending => text.replace("\r\n", "\n").replace('\n', ending),
+        }
+    }
+}
+
+impl FromStr for LineEnding {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "lf" => Ok(LineEnding::Lf),
+            "crlf" => Ok(LineEnding::Crlf),
+            "platform" => Ok(LineEnding::Platform),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Trim leading/trailing whitespace and collapse runs of spaces and tabs into
+/// a single space. Newlines from multi-line edits are kept, minus any
+/// whitespace around them.
+pub fn normalize(text: &str) -> String {
+    text.trim()
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Strip control characters (escape sequences, carriage returns, ...) other
+/// than newline and tab, so the text can't drive the terminal or a shell it
+/// is pasted into. `--raw` skips this.
+pub fn sanitize(text: &str) -> Cow<'_, str> {
+    let is_unsafe = |c: char| c.is_control() && c != '\n' && c != '\t';
+    if text.contains(is_unsafe) {
+        Cow::Owned(text.chars().filter(|&c| !is_unsafe(c)).collect())
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Write the final transcription to stdout, followed by a line break unless
+/// `--no-newline` was given
+pub fn print(text: &str, newline: Option<LineEnding>) {
+    let mut stdout = io::stdout().lock();
+    let ending = newline.map_or("", LineEnding::as_str);
+    // The process exits right after, which skips flushing stdout
+    let result = write!(stdout, "{}{}", text, ending).and_then(|_| stdout.flush());
+    report_write_error(result);
+}
+
+/// Writes the phrases the recognizer has finished to stdout as they come
+/// (`--stream`), flushing each so a reader downstream sees it right away
+pub struct LineStream {
+    out: BufWriter<Stdout>,
+    ending: LineEnding,
+    raw: bool,
+    filter: Option<WordFilter>,
+    written: usize, // Bytes of the committed text already written
+    closed: bool,
+}
+
+impl LineStream {
+    pub fn new(ending: LineEnding, raw: bool, filter: Option<WordFilter>) -> Self {
+        Self {
+            out: BufWriter::new(io::stdout()),
+            ending,
+            raw,
+            filter,
+            written: 0,
+            closed: false,
+        }
+    }
+
+    /// Write what `committed` (the finished part of the transcription) adds
+    /// to what was written before, as a line of its own. Returns false once
+    /// the reader has gone away.
+    pub fn update(&mut self, committed: &str) -> bool {
+        let Some(added) = committed.get(self.written..) else {
+            return !self.closed; // Voice commands changed the text written; wait for more
+        };
+        let line = normalize(added);
+        self.written = committed.len();
+        if line.is_empty() {
+            return !self.closed;
+        }
+        let line = match &self.filter {
+            Some(filter) => filter.apply(&line).into_owned(),
+            None => line,
+        };
+        let line = if self.raw {
+            line
+        } else {
+            sanitize(&line).into_owned()
+        };
+        self.write_line(&line)
+    }
+
+    /// Start over for a new recognition session, whose transcription starts
+    /// out empty
+    pub fn new_session(&mut self) {
+        self.written = 0;
+    }
+
+    /// Write `line` and a line break, returning false once the reader has
+    /// gone away
+    pub fn write_line(&mut self, line: &str) -> bool {
+        if self.closed {
+            return false;
+        }
+        let line = self.ending.apply(line.to_string());
+        let result =
+            write!(self.out, "{}{}", line, self.ending.as_str()).and_then(|_| self.out.flush());
+        if result.is_err() {
+            self.closed = true;
+        }
+        report_write_error(result);
+        !self.closed
+    }
+}
+
+/// A reader that went away (`claudio | head -1`) isn't an error worth
+/// reporting; anything else is logged
+fn report_write_error(result: io::Result<()>) {
+    match result {
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => {
+            log::debug!("stdout was closed by the reader");
+        }
+        Err(e) => log::warn!("Failed to write to stdout: {}", e),
+        Ok(()) => {}
+    }
+}
+
+/// The first `max_chars` characters of `text` (`--max-chars`)
+pub fn truncate(text: &str, max_chars: usize) -> &str {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+/// Add the `--prepend`/`--append` text around the transcription. An empty
+/// transcription stays empty, so nothing is output for a silent session.
+pub fn wrap(text: String, prepend: Option<&str>, append: Option<&str>) -> String {
+    if text.is_empty() {
+        return text;
+    }
+    format!(
+        "{}{}{}",
+        prepend.unwrap_or_default(),
+        text,
+        append.unwrap_or_default()
+    )
+}
@@ -0,0 +1,120 @@
+//! High-level recording session for embedding
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{self, Receiver},
+    Arc,
+};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::speech::{RecognizerOptions, SharedState, SpeechRecognizer};
+
+const POLL_INTERVAL_MS: u64 = 33;
+
+/// A running speech recognition session.
+///
+/// Transcription updates arrive on [`Session::updates`] as the full text so
+/// far, whenever it changes. The channel closes once the recognizer stops on
+/// its own (e.g. a final result or an error) or the session is finished;
+/// [`Session::wait`] blocks until the former happens.
+pub struct Session {
+    recognizer: SpeechRecognizer,
+    shared: SharedState,
+    updates: Receiver<String>,
+    stop_signal: Arc<AtomicBool>,
+    watcher: Option<thread::JoinHandle<()>>,
+}
+
+impl Session {
+    /// Start listening on the default microphone
+    pub fn start(options: RecognizerOptions) -> Result<Self> {
+        let shared = SharedState::default();
+        let mut recognizer = SpeechRecognizer::new(shared.clone(), options)?;
+        recognizer.start()?;
+
+        let (sender, updates) = mpsc::channel();
+        let stop_signal = Arc::new(AtomicBool::new(false));
+
+        // Backends only expose shared state, so watch it for changes
+        let watcher = {
+            let shared = shared.clone();
+            let stop_signal = Arc::clone(&stop_signal);
+            thread::spawn(move || {
+                let mut last_sent = String::new();
+                while !stop_signal.load(Ordering::SeqCst) {
+                    // Checked before reading so the final text is always sent
+                    let done = shared.completion.is_done();
+
+                    let text = shared.transcription.lock().unwrap().clone();
+                    if text != last_sent {
+                        if sender.send(text.clone()).is_err() {
+                            break; // Receiver dropped
+                        }
+                        last_sent = text;
+                    }
+
+                    if done {
+                        break;
+                    }
+                    // Wakes early on completion so the final text is sent promptly
+                    shared
+                        .completion
+                        .wait_timeout(Duration::from_millis(POLL_INTERVAL_MS));
+                }
+            })
+        };
+
+        Ok(Self {
+            recognizer,
+            shared,
+            updates,
+            stop_signal,
+            watcher: Some(watcher),
+        })
+    }
+
+    /// Channel of transcription updates (full text so far)
+    pub fn updates(&self) -> &Receiver<String> {
+        &self.updates
+    }
+
+    /// Warmup is done and it's safe to start speaking
+    pub fn is_ready(&self) -> bool {
+        self.shared.is_ready.load(Ordering::SeqCst)
+    }
+
+    /// Recognition error reported by the backend, if any
+    pub fn error(&self) -> Option<String> {
+        self.shared.error.lock().unwrap().clone()
+    }
+
+    /// Block until recognition ends on its own (final result or error) or
+    /// `timeout` elapses; returns whether it ended
+    pub fn wait(&self, timeout: Duration) -> bool {
+        self.shared.completion.wait_timeout(timeout)
+    }
+
+    /// Stop listening and return the final transcription
+    pub fn finish(mut self) -> String {
+        self.stop();
+        // Stopping may deliver a final result, so read the transcription afterwards
+        self.shared.transcription.lock().unwrap().clone()
+    }
+
+    fn stop(&mut self) {
+        self.recognizer.stop();
+        self.stop_signal.store(true, Ordering::SeqCst);
+        if let Some(watcher) = self.watcher.take() {
+            let _ = watcher.join();
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,180 @@
+//! Destinations for the final transcription
+//!
+//! Stdout is the default destination. `--output FILE` and `--clipboard`
+//! replace it and can be combined, while `--clipboard-append` and
+//! `--webhook` get the text in addition. A `--` command isn't a sink: its
+//! exit status becomes claudio's and `--confirm` may stop it, so main runs
+//! it itself.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+
+use crate::cli::Args;
+use crate::output::{self, LineEnding};
+use crate::{clipboard, webhook};
+
+pub trait OutputSink {
+    /// What delivering does, for `--dry-run` ("print the transcription to
+    /// stdout")
+    fn action(&self) -> String;
+
+    /// Hand over the final transcription
+    fn deliver(&self, text: &str) -> Result<()>;
+
+    /// Whether a failure is only reported instead of failing the run
+    fn best_effort(&self) -> bool {
+        false
+    }
+}
+
+/// Print to stdout
+pub struct Stdout {
+    newline: Option<LineEnding>,
+}
+
+impl OutputSink for Stdout {
+    fn action(&self) -> String {
+        "print the transcription to stdout".to_string()
+    }
+
+    fn deliver(&self, text: &str) -> Result<()> {
+        output::print(text, self.newline);
+        Ok(())
+    }
+}
+
+/// Write to a file, replacing it (`--output FILE`)
+pub struct File {
+    path: PathBuf,
+    newline: Option<LineEnding>,
+}
+
+impl OutputSink for File {
+    fn action(&self) -> String {
+        format!("write the transcription to {}", self.path.display())
+    }
+
+    fn deliver(&self, text: &str) -> Result<()> {
+        let mut contents = text.to_string();
+        if let Some(ending) = self.newline {
+            contents.push_str(ending.as_str());
+        }
+        fs::write(&self.path, contents)
+            .map_err(|e| anyhow!("Failed to write {}: {}", self.path.display(), e))
+    }
+}
+
+/// Replace the clipboard text (`--clipboard`)
+pub struct Clipboard;
+
+impl OutputSink for Clipboard {
+    fn action(&self) -> String {
+        "copy the transcription to the clipboard".to_string()
+    }
+
+    fn deliver(&self, text: &str) -> Result<()> {
+        clipboard::set(text)
+    }
+}
+
+/// Add to the end of the clipboard (`--clipboard-append`)
+pub struct ClipboardAppend;
+
+impl OutputSink for ClipboardAppend {
+    fn action(&self) -> String {
+        "append the transcription to the clipboard".to_string()
+    }
+
+    fn deliver(&self, text: &str) -> Result<()> {
+        clipboard::append(text)
+    }
+}
+
+/// POST as JSON (`--webhook URL`)
+pub struct Webhook {
+    url: String,
+    duration: Duration,
+    backend: &'static str,
+}
+
+impl OutputSink for Webhook {
+    fn action(&self) -> String {
+        format!("post the transcription to {}", self.url)
+    }
+
+    fn deliver(&self, text: &str) -> Result<()> {
+        webhook::post(&self.url, text, self.duration, self.backend)
+    }
+
+    fn best_effort(&self) -> bool {
+        true
+    }
+}
+
+/// The sinks selected on the command line
+pub struct Sinks {
+    /// Get the text in place of stdout, even what a failed session
+    /// transcribed; empty when only the `--` command or `--stream` gets it
+    pub destinations: Vec<Box<dyn OutputSink>>,
+    /// Get the text in addition, only after a clean session
+    pub extras: Vec<Box<dyn OutputSink>>,
+}
+
+impl Sinks {
+    /// `duration` is how long recording took, for `--webhook`
+    pub fn from_args(args: &Args, duration: Duration) -> Self {
+        let newline = (!args.no_newline).then_some(args.line_ending);
+
+        let mut destinations: Vec<Box<dyn OutputSink>> = Vec::new();
+        if let Some(path) = &args.output {
+            destinations.push(Box::new(File {
+                path: path.clone(),
+                newline,
+            }));
+        }
+        if args.clipboard {
+            destinations.push(Box::new(Clipboard));
+        }
+        // --stream already printed the text as it came
+        if destinations.is_empty() && args.exec_command.is_none() && !args.stream {
+            destinations.push(Box::new(Stdout { newline }));
+        }
+
+        let mut extras: Vec<Box<dyn OutputSink>> = Vec::new();
+        if let Some(url) = &args.webhook {
+            extras.push(Box::new(Webhook {
+                url: url.clone(),
+                duration,
+                backend: args.recognizer.engine.resolve().name(),
+            }));
+        }
+        if args.clipboard_append {
+            extras.push(Box::new(ClipboardAppend));
+        }
+
+        Self {
+            destinations,
+            extras,
+        }
+    }
+}
+
+/// Deliver `text` to each sink (or describe it with `--dry-run`), reporting
+/// failures on stderr. Returns how many failures should fail the run.
+pub fn deliver(sinks: &[Box<dyn OutputSink>], text: &str, dry_run: bool) -> usize {
+    let mut failed = 0;
+    for sink in sinks {
+        if dry_run {
+            eprintln!("Would {}", sink.action());
+        } else if let Err(e) = sink.deliver(text) {
+            eprintln!("{}", e);
+            if !sink.best_effort() {
+                failed += 1;
+            }
+        }
+    }
+    failed
+}
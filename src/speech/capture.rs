@@ -0,0 +1,339 @@
+//! Microphone capture shared by the cpal-based backends (Vosk, Whisper)
+//!
+//! Audio is averaged to mono, amplified by `--gain`, resampled to 16 kHz and
+//! queued as i16 samples for the recognition thread.
+
+use std::collections::VecDeque;
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Condvar, Mutex,
+};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+
+use super::{Activity, Gain};
+
+/// Sample rate the models expect; audio is captured or resampled to it
+pub const SAMPLE_RATE: u32 = 16000;
+
+/// RMS level `--gain auto` scales speech toward
+const AUTO_GAIN_TARGET_RMS: f32 = 0.1;
+/// Buffers quieter than this are treated as silence and don't adjust the gain
+const AUTO_GAIN_NOISE_FLOOR: f32 = 0.002;
+/// Most `--gain auto` amplifies, so background noise isn't blown up
+const AUTO_GAIN_MAX: f32 = 20.0;
+/// How often the applied gain is logged
+const GAIN_LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The default input device and its default format, for `claudio doctor`
+pub fn describe_input() -> Result<String> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or_else(|| anyhow!("No input device available"))?;
+    let config = device.default_input_config()?;
+    Ok(format!(
+        "{} ({} Hz, {} channels)",
+        device.name().unwrap_or_default(),
+        config.sample_rate().0,
+        config.channels()
+    ))
+}
+
+/// Open the default input device and start capturing mono i16 samples at
+/// `SAMPLE_RATE` into `audio_queue`, marking `audio` on every buffer.
+/// Buffers arriving while `muted` is set are only marked. This runs on the
+/// device's real-time callback, so `--save-audio` is written by the thread
+/// that takes the samples off the queue.
+pub fn open_input(
+    audio_queue: Arc<AudioQueue>,
+    audio: Arc<Activity>,
+    muted: Arc<AtomicBool>,
+    gain: Gain,
+) -> Result<cpal::Stream> {
+    let host = cpal::default_host();
+    let device = host
+        .default_input_device()
+        .ok_or_else(|| anyhow!("No input device available"))?;
+
+    // Prefer capturing at the target rate; otherwise take the device
+    // default and resample
+    let target_rate = cpal::SampleRate(SAMPLE_RATE);
+    let config = device
+        .supported_input_configs()?
+        .find(|c| {
+            c.min_sample_rate() <= target_rate
+                && target_rate <= c.max_sample_rate()
+                && matches!(
+                    c.sample_format(),
+                    SampleFormat::F32 | SampleFormat::I16 | SampleFormat::U16
+                )
+        })
+        .map(|c| c.with_sample_rate(target_rate))
+        .map_or_else(|| device.default_input_config(), Ok)?;
+
+    let sample_format = config.sample_format();
+    let device_rate = config.sample_rate().0;
+    let channels = config.channels() as usize;
+    log::debug!(
+        "Opening input device {} ({} Hz, {} channels, {:?})",
+        device.name().unwrap_or_default(),
+        device_rate,
+        channels,
+        sample_format
+    );
+
+    let mut resampler = Resampler::new(device_rate, SAMPLE_RATE);
+    let mut gain = GainControl::new(gain);
+    let on_audio = move |mono: &mut [f32]| {
+        audio.mark();
+        if muted.load(Ordering::Relaxed) {
+            return;
+        }
+        gain.apply(mono);
+        let mut samples = Vec::with_capacity(mono.len());
+        resampler.process(mono, &mut samples);
+        audio_queue.push(&samples);
+    };
+
+    let config = config.into();
+    let stream = match sample_format {
+        SampleFormat::F32 => build_stream::<f32>(&device, &config, channels, on_audio),
+        SampleFormat::I16 => build_stream::<i16>(&device, &config, channels, on_audio),
+        SampleFormat::U16 => build_stream::<u16>(&device, &config, channels, on_audio),
+        other => Err(anyhow!("Unsupported input sample format: {:?}", other)),
+    }?;
+
+    stream.play()?;
+    Ok(stream)
+}
+
+/// Build an input stream that averages each frame to mono f32 and hands
+/// the samples to `on_audio`
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    channels: usize,
+    mut on_audio: impl FnMut(&mut [f32]) + Send + 'static,
+) -> Result<cpal::Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let stream = device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            let mut mono: Vec<f32> = data
+                .chunks(channels)
+                .map(|frame| {
+                    let sum: f32 = frame.iter().map(|&s| s.to_sample::<f32>()).sum();
+                    sum / channels as f32
+                })
+                .collect();
+            on_audio(&mut mono);
+        },
+        |err| {
+            log::error!("Audio stream error: {}", err);
+        },
+        None,
+    )?;
+    Ok(stream)
+}
+
+/// Applies `--gain` to captured audio. In auto mode the factor follows the
+/// level of recent speech: it drops quickly when input gets loud, so peaks
+/// don't clip, and rises slowly toward `AUTO_GAIN_TARGET_RMS`.
+struct GainControl {
+    auto: bool,
+    factor: f32,
+    last_logged: Option<Instant>,
+}
+
+impl GainControl {
+    fn new(gain: Gain) -> Self {
+        let (auto, factor) = match gain {
+            Gain::Fixed(factor) => (false, factor),
+            Gain::Auto => (true, 1.0),
+        };
+        Self {
+            auto,
+            factor,
+            last_logged: None,
+        }
+    }
+
+    fn apply(&mut self, samples: &mut [f32]) {
+        if self.auto && !samples.is_empty() {
+            let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+            let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+            if rms > AUTO_GAIN_NOISE_FLOOR {
+                let target = (AUTO_GAIN_TARGET_RMS / rms)
+                    .min(0.99 / peak)
+                    .clamp(1.0, AUTO_GAIN_MAX);
+                let rate = if target < self.factor { 0.5 } else { 0.05 };
+                self.factor += (target - self.factor) * rate;
+            }
+        }
+
+        if self
+            .last_logged
+            .is_none_or(|logged| logged.elapsed() >= GAIN_LOG_INTERVAL)
+            && (self.auto || self.factor != 1.0)
+        {
+            log::debug!("Input gain {:.2}x", self.factor);
+            self.last_logged = Some(Instant::now());
+        }
+
+        if self.factor != 1.0 {
+            for sample in samples {
+                *sample = (*sample * self.factor).clamp(-1.0, 1.0);
+            }
+        }
+    }
+}
+
+/// Bounded queue of captured samples between the audio callback and the
+/// processing thread. When full, the oldest samples are dropped so memory and
+/// latency stay bounded if the recognizer can't keep up.
+pub struct AudioQueue {
+    samples: Mutex<VecDeque<i16>>,
+    available: Condvar,
+    capacity: usize,
+    dropped: AtomicUsize,
+}
+
+impl AudioQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: Mutex::new(VecDeque::with_capacity(capacity)),
+            available: Condvar::new(),
+            capacity,
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Append samples, evicting the oldest beyond capacity, and wake the reader
+    pub fn push(&self, samples: &[i16]) {
+        let Ok(mut queue) = self.samples.lock() else {
+            return;
+        };
+        queue.extend(samples);
+        let excess = queue.len().saturating_sub(self.capacity);
+        if excess > 0 {
+            queue.drain(..excess);
+            self.dropped.fetch_add(excess, Ordering::Relaxed);
+        }
+        self.available.notify_one();
+    }
+
+    /// Take everything queued, waiting up to `timeout` for audio to arrive
+    pub fn take(&self, timeout: Duration) -> Vec<i16> {
+        let queue = self.samples.lock().unwrap();
+        let (mut queue, _) = self
+            .available
+            .wait_timeout_while(queue, timeout, |q| q.is_empty())
+            .unwrap();
+        queue.drain(..).collect()
+    }
+
+    /// Number of samples dropped since the last call
+    pub fn take_dropped(&self) -> usize {
+        self.dropped.swap(0, Ordering::Relaxed)
+    }
+}
+
+/// Convert a [-1.0, 1.0] sample to i16, clamping loud input instead of wrapping
+pub fn to_i16(sample: f32) -> i16 {
+    (sample * 32768.0).clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
+
+/// Linear-interpolation resampler from the device rate to `SAMPLE_RATE`.
+/// Output samples are clamped to the i16 range.
+struct Resampler {
+    step: f64, // Input samples per output sample
+    pos: f64,  // Position of the next output sample between `prev` and the next input
+    prev: f32,
+}
+
+impl Resampler {
+    fn new(from_rate: u32, to_rate: u32) -> Self {
+        Self {
+            step: from_rate as f64 / to_rate as f64,
+            pos: 1.0,
+            prev: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: &[f32], out: &mut Vec<i16>) {
+        if self.step == 1.0 {
+            out.extend(input.iter().map(|&s| to_i16(s)));
+            return;
+        }
+        for &sample in input {
+            while self.pos <= 1.0 {
+                let value = self.prev + (sample - self.prev) * self.pos as f32;
+                out.push(to_i16(value));
+                self.pos += self.step;
+            }
+            self.pos -= 1.0;
+            self.prev = sample;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `seconds` of a 440 Hz sine at `rate`, with the given peak amplitude
+    fn sine(rate: u32, amplitude: f32, seconds: f32) -> Vec<f32> {
+        let len = (rate as f32 * seconds) as usize;
+        (0..len)
+            .map(|i| {
+                let t = i as f32 / rate as f32;
+                amplitude * (2.0 * std::f32::consts::PI * 440.0 * t).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn full_scale_sine_does_not_wrap() {
+        for rate in [SAMPLE_RATE, 44100, 48000] {
+            // Full scale, and louder than that as boosted input can be
+            for amplitude in [1.0, 1.5] {
+                let mut resampler = Resampler::new(rate, SAMPLE_RATE);
+                let mut out = Vec::new();
+                // In several buffers, as the audio callback delivers it
+                for chunk in sine(rate, amplitude, 0.5).chunks(512) {
+                    resampler.process(chunk, &mut out);
+                }
+
+                let expected = SAMPLE_RATE as usize / 2;
+                assert!(
+                    out.len().abs_diff(expected) <= 2,
+                    "{rate} Hz: {}",
+                    out.len()
+                );
+                // Wrapping would flip a peak to the other extreme; a 440 Hz
+                // sine at 16 kHz moves at most ~5700 per sample
+                for pair in out.windows(2) {
+                    let step = (pair[1] as i32 - pair[0] as i32).abs();
+                    assert!(step < 10_000, "{rate} Hz x{amplitude}: {pair:?}");
+                }
+                assert!(*out.iter().max().unwrap() > 32_000);
+                assert!(*out.iter().min().unwrap() < -32_000);
+            }
+        }
+    }
+
+    #[test]
+    fn to_i16_clamps() {
+        assert_eq!(to_i16(1.0), i16::MAX);
+        assert_eq!(to_i16(2.0), i16::MAX);
+        assert_eq!(to_i16(-1.0), i16::MIN);
+        assert_eq!(to_i16(-2.0), i16::MIN);
+        assert_eq!(to_i16(0.0), 0);
+    }
+}
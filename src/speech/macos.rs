@@ -1,26 +1,35 @@
 //! macOS speech recognition using the native Speech framework.
 
+use std::sync::OnceLock;
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU32, Ordering},
     Arc, Mutex,
 };
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Result};
 use block2::RcBlock;
 use objc2::rc::Retained;
+use objc2::runtime::{AnyObject, NSObjectProtocol, ProtocolObject};
 use objc2::AllocAnyThread;
-use objc2_avf_audio::{AVAudioEngine, AVAudioPCMBuffer, AVAudioTime};
-use objc2_foundation::{NSError, NSLocale, NSOperationQueue};
+use objc2_avf_audio::{
+    AVAudioEngine, AVAudioEngineConfigurationChangeNotification, AVAudioPCMBuffer, AVAudioTime,
+};
+use objc2_foundation::{
+    NSArray, NSError, NSLocale, NSNotification, NSNotificationCenter, NSOperationQueue, NSString,
+};
 use objc2_speech::{
     SFSpeechAudioBufferRecognitionRequest, SFSpeechRecognitionResult, SFSpeechRecognitionTask,
-    SFSpeechRecognizer, SFSpeechRecognizerAuthorizationStatus,
+    SFSpeechRecognitionTaskHint, SFSpeechRecognizer, SFSpeechRecognizerAuthorizationStatus,
 };
 use std::ptr::NonNull;
 
+use super::{publish, retry, Check, RecognizerOptions, SharedState, TaskHint};
+
 type TapBlock = RcBlock<dyn Fn(NonNull<AVAudioPCMBuffer>, NonNull<AVAudioTime>)>;
 type RecognitionHandler = RcBlock<dyn Fn(*mut SFSpeechRecognitionResult, *mut NSError)>;
+type NotificationHandler = RcBlock<dyn Fn(NonNull<NSNotification>)>;
 
 pub struct SpeechRecognizerImpl {
     recognizer: Retained<SFSpeechRecognizer>,
@@ -30,31 +39,77 @@ pub struct SpeechRecognizerImpl {
     transcription: Arc<Mutex<String>>,
     is_listening: Arc<AtomicBool>,
     is_ready: Arc<AtomicBool>,
+    is_reconnecting: Arc<AtomicBool>,
+    shared: SharedState,
+    // Cleared by stop(); callbacks from a stopped session check it and do
+    // nothing, so late results/errors can't leak into the next session
+    session_active: Arc<AtomicBool>,
+    warmup: Duration,
+    final_only: bool,
+    task_hint: TaskHint,
+    vocabulary: Vec<String>,
+    // Restarts the engine when the input device changes (headphones
+    // unplugged, a new default input); handled on its own queue since the
+    // restart may wait and retry
+    device_observer: Option<Retained<ProtocolObject<dyn NSObjectProtocol>>>,
+    device_queue: Retained<NSOperationQueue>,
     // Keep blocks alive
     _tap_block: Option<TapBlock>,
     _handler: Option<RecognitionHandler>,
+    _device_handler: Option<NotificationHandler>,
 }
 
 impl SpeechRecognizerImpl {
-    pub fn new(
-        transcription: Arc<Mutex<String>>,
-        is_listening: Arc<AtomicBool>,
-        is_ready: Arc<AtomicBool>,
-    ) -> Result<Self> {
-        // Create speech recognizer with default locale
-        let recognizer = unsafe {
-            let locale = NSLocale::currentLocale();
-            SFSpeechRecognizer::initWithLocale(SFSpeechRecognizer::alloc(), &locale)
-        }
-        .ok_or_else(|| anyhow!("Failed to create speech recognizer"))?;
+    pub fn new(shared: SharedState, options: RecognizerOptions) -> Result<Self> {
+        // Create speech recognizer with the first supported requested
+        // locale, or the default one
+        let recognizer = if options.languages.is_empty() {
+            unsafe {
+                SFSpeechRecognizer::initWithLocale(
+                    SFSpeechRecognizer::alloc(),
+                    &NSLocale::currentLocale(),
+                )
+            }
+            .ok_or_else(|| anyhow!("Failed to create speech recognizer"))?
+        } else {
+            options
+                .languages
+                .iter()
+                .find_map(|tag| {
+                    let locale = unsafe {
+                        NSLocale::initWithLocaleIdentifier(
+                            NSLocale::alloc(),
+                            &NSString::from_str(tag),
+                        )
+                    };
+                    let recognizer = unsafe {
+                        SFSpeechRecognizer::initWithLocale(SFSpeechRecognizer::alloc(), &locale)
+                    };
+                    match &recognizer {
+                        Some(_) => log::info!("Using language {}", tag),
+                        None => log::debug!("Language {} is not supported", tag),
+                    }
+                    recognizer
+                })
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Speech recognition is not supported for language '{}'",
+                        options.languages.join("', '")
+                    )
+                })?
+        };
 
-        // Check if speech recognition is available
-        let available = unsafe { recognizer.isAvailable() };
-        if !available {
-            return Err(anyhow!(
-                "Speech recognition is not available. Please check system permissions."
-            ));
-        }
+        // Check if speech recognition is available (can briefly be false
+        // right after login or while another app holds the mic, so retry)
+        retry(&shared.is_reconnecting, || {
+            if unsafe { recognizer.isAvailable() } {
+                Ok(())
+            } else {
+                Err(anyhow!(
+                    "Speech recognition is not available. Please check system permissions."
+                ))
+            }
+        })?;
 
         // Set a custom operation queue for callbacks (CLI apps don't have a main run loop)
         let queue = NSOperationQueue::new();
@@ -62,6 +117,8 @@ impl SpeechRecognizerImpl {
             recognizer.setQueue(&queue);
         }
 
+        shared.set_locale(unsafe { recognizer.locale().localeIdentifier() }.to_string());
+
         // Create audio engine
         let audio_engine = unsafe { AVAudioEngine::new() };
 
@@ -70,15 +127,32 @@ impl SpeechRecognizerImpl {
             audio_engine,
             request: None,
             task: None,
-            transcription,
-            is_listening,
-            is_ready,
+            transcription: shared.transcription.clone(),
+            is_listening: shared.is_listening.clone(),
+            is_ready: shared.is_ready.clone(),
+            is_reconnecting: shared.is_reconnecting.clone(),
+            shared,
+            session_active: Arc::new(AtomicBool::new(false)),
+            warmup: Duration::from_millis(options.warmup_ms),
+            final_only: options.final_only,
+            task_hint: options.task_hint,
+            // The Speech framework can't be restricted to --grammar, only
+            // steered towards it
+            vocabulary: [options.vocabulary, options.grammar].concat(),
+            device_observer: None,
+            device_queue: NSOperationQueue::new(),
             _tap_block: None,
             _handler: None,
+            _device_handler: None,
         })
     }
 
     pub fn start(&mut self) -> Result<()> {
+        // Never install a second tap on top of a running session
+        if self.task.is_some() {
+            self.stop();
+        }
+
         // Check authorization status
         let auth_status = unsafe { SFSpeechRecognizer::authorizationStatus() };
 
@@ -130,24 +204,51 @@ impl SpeechRecognizerImpl {
         let request = unsafe { SFSpeechAudioBufferRecognitionRequest::new() };
 
         unsafe {
-            request.setShouldReportPartialResults(true);
+            // With --no-partial the text only arrives as the final result
+            request.setShouldReportPartialResults(!self.final_only);
+            request.setTaskHint(match self.task_hint {
+                TaskHint::Unspecified => SFSpeechRecognitionTaskHint::Unspecified,
+                TaskHint::Dictation => SFSpeechRecognitionTaskHint::Dictation,
+                TaskHint::Search => SFSpeechRecognitionTaskHint::Search,
+                TaskHint::Confirmation => SFSpeechRecognitionTaskHint::Confirmation,
+            });
+            if !self.vocabulary.is_empty() {
+                let strings: Vec<_> = self
+                    .vocabulary
+                    .iter()
+                    .map(|term| NSString::from_str(term))
+                    .collect();
+                request.setContextualStrings(&NSArray::from_retained_slice(&strings));
+            }
         }
 
-        // Get input node
-        let input_node = unsafe { self.audio_engine.inputNode() };
-
-        // Get recording format
-        let format = unsafe { input_node.outputFormatForBus(0) };
-
         // Set up the recognition handler
         let transcription = Arc::clone(&self.transcription);
+        let text_changed = Arc::clone(&self.shared.text_changed);
         let is_listening = Arc::clone(&self.is_listening);
         let is_listening_for_tap = Arc::clone(&self.is_listening);
         let is_ready_for_tap = Arc::clone(&self.is_ready);
+        let audio_for_tap = Arc::clone(&self.shared.audio);
+        let recording_for_tap = Arc::clone(&self.shared.recording);
+        let muted_for_tap = Arc::clone(&self.shared.muted);
+        // Follows the input format, which changes with the device
+        let sample_rate = Arc::new(AtomicU32::new(0));
+        let sample_rate_for_tap = Arc::clone(&sample_rate);
+        let shared = self.shared.clone();
+        self.session_active = Arc::new(AtomicBool::new(true));
+        let session_active = Arc::clone(&self.session_active);
+        let session_active_for_tap = Arc::clone(&self.session_active);
 
         let handler = RcBlock::new(
             move |result: *mut SFSpeechRecognitionResult, error: *mut NSError| {
+                // Includes the cancellation error from our own stop()
+                if !session_active.load(Ordering::SeqCst) {
+                    return;
+                }
+
                 if !error.is_null() {
+                    let description = unsafe { (*error).localizedDescription() };
+                    shared.report_error(format!("Recognition error: {}", description));
                     return;
                 }
 
@@ -160,13 +261,13 @@ impl SpeechRecognizerImpl {
                 let formatted_string = unsafe { best_transcription.formattedString() };
                 let text = formatted_string.to_string();
 
-                if let Ok(mut trans) = transcription.lock() {
-                    *trans = text;
-                }
+                publish(&transcription, &text_changed, &text);
 
                 let is_final = unsafe { result.isFinal() };
                 if is_final {
+                    log::info!("Final result received");
                     is_listening.store(false, Ordering::SeqCst);
+                    shared.completion.signal();
                 }
             },
         );
@@ -179,45 +280,95 @@ impl SpeechRecognizerImpl {
 
         // Install tap on input node to capture audio
         let request_for_tap = request.clone();
-        let buffer_count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let first_buffer_at: Arc<OnceLock<Instant>> = Arc::new(OnceLock::new());
+        let warmup = self.warmup;
         let tap_block = RcBlock::new(
             move |buffer: NonNull<AVAudioPCMBuffer>, _when: NonNull<AVAudioTime>| {
-                // Count audio buffers and set ready after warmup period
-                let count = buffer_count.fetch_add(1, Ordering::SeqCst);
-                if count >= 10 {
-                    // After ~10 buffers (~200ms at 1024 samples/buffer), we're ready
-                    is_ready_for_tap.store(true, Ordering::SeqCst);
+                // Buffers can still be in flight on the audio thread after stop()
+                if !session_active_for_tap.load(Ordering::SeqCst) {
+                    return;
+                }
+
+                audio_for_tap.mark();
+                // --push-to-talk: drop audio while the key is up
+                let muted = muted_for_tap.load(Ordering::SeqCst);
+                if !muted && recording_for_tap.is_active() {
+                    let samples = unsafe { first_channel(buffer.as_ref()) };
+                    recording_for_tap.write(&samples, sample_rate_for_tap.load(Ordering::SeqCst));
+                }
+
+                // Set ready once audio has been flowing for the warmup period.
+                // Time-based so it doesn't depend on the negotiated buffer size/sample rate.
+                let first = *first_buffer_at.get_or_init(Instant::now);
+                if first.elapsed() >= warmup {
+                    if !is_ready_for_tap.swap(true, Ordering::SeqCst) {
+                        log::info!("Recognizer ready after {:?} warmup", first.elapsed());
+                    }
                     is_listening_for_tap.store(true, Ordering::SeqCst);
                 }
-                unsafe {
-                    request_for_tap.appendAudioPCMBuffer(buffer.as_ref());
+                if !muted {
+                    unsafe {
+                        request_for_tap.appendAudioPCMBuffer(buffer.as_ref());
+                    }
                 }
             },
         );
 
         unsafe {
-            // Convert RcBlock to raw pointer for the C API
-            let tap_block_ptr = &*tap_block as *const block2::Block<_> as *mut block2::Block<_>;
-            input_node.installTapOnBus_bufferSize_format_block(
-                0,
-                1024,
-                Some(&format),
-                tap_block_ptr,
-            );
+            install_tap(&self.audio_engine, &tap_block, &sample_rate);
         }
 
-        // Prepare and start audio engine
+        // Prepare and start audio engine (retried while the input is busy)
+        log::debug!("Starting audio engine");
         unsafe {
             self.audio_engine.prepare();
+        }
+        retry(&self.is_reconnecting, || unsafe {
             self.audio_engine
                 .startAndReturnError()
-                .map_err(|e| anyhow!("Failed to start audio engine: {:?}", e))?;
-        }
+                .map_err(|e| anyhow!("Failed to start audio engine: {:?}", e))
+        })?;
+
+        // A device change stops the engine; put the tap back in the new
+        // input format and restart it, keeping the recognition task (and
+        // so the transcription) going
+        let engine = self.audio_engine.clone();
+        let tap_block_for_restart = tap_block.clone();
+        let session_active = Arc::clone(&self.session_active);
+        let shared = self.shared.clone();
+        let device_handler = RcBlock::new(move |_notification: NonNull<NSNotification>| {
+            if !session_active.load(Ordering::SeqCst) {
+                return;
+            }
+            log::warn!("Audio device changed, restarting the audio engine");
+            shared.device_changed.mark();
+            let restarted = retry(&shared.is_reconnecting, || unsafe {
+                install_tap(&engine, &tap_block_for_restart, &sample_rate);
+                engine.prepare();
+                engine
+                    .startAndReturnError()
+                    .map_err(|e| anyhow!("Failed to restart audio engine: {:?}", e))
+            });
+            if let Err(e) = restarted {
+                shared.report_error(format!("Audio device changed: {}", e));
+            }
+        });
+        let engine: &AnyObject = &self.audio_engine;
+        let observer = unsafe {
+            NSNotificationCenter::defaultCenter().addObserverForName_object_queue_usingBlock(
+                Some(AVAudioEngineConfigurationChangeNotification),
+                Some(engine),
+                Some(&self.device_queue),
+                &device_handler,
+            )
+        };
 
         self.request = Some(request);
         self.task = Some(task);
+        self.device_observer = Some(observer);
         self._tap_block = Some(tap_block);
         self._handler = Some(handler);
+        self._device_handler = Some(device_handler);
 
         // is_listening will be set to true by the tap callback once audio is flowing
 
@@ -225,12 +376,26 @@ impl SpeechRecognizerImpl {
     }
 
     pub fn stop(&mut self) {
+        log::debug!("Stopping recognition");
+        self.session_active.store(false, Ordering::SeqCst);
         self.is_listening.store(false, Ordering::SeqCst);
 
+        if let Some(observer) = self.device_observer.take() {
+            let observer: &AnyObject = (*observer).as_ref();
+            unsafe {
+                NSNotificationCenter::defaultCenter().removeObserver(observer);
+            }
+        }
+
+        // Synchronous: once stop returns the engine is halted and the tap is
+        // gone, so a new session can start immediately. Resetting also
+        // releases the input device, so the mic indicator goes off and other
+        // apps can record once we're done.
         unsafe {
             self.audio_engine.stop();
             let input_node = self.audio_engine.inputNode();
             input_node.removeTapOnBus(0);
+            self.audio_engine.reset();
         }
 
         if let Some(ref request) = self.request {
@@ -249,11 +414,85 @@ impl SpeechRecognizerImpl {
         self.task = None;
         self._tap_block = None;
         self._handler = None;
+        self._device_handler = None;
     }
 }
 
+/// Locales the Speech framework supports, for `claudio locales`
+pub fn supported_languages() -> Result<Vec<String>> {
+    let locales = unsafe { SFSpeechRecognizer::supportedLocales() };
+    Ok(locales
+        .allObjects()
+        .iter()
+        .map(|locale| unsafe { locale.localeIdentifier() }.to_string())
+        .collect())
+}
+
+/// `claudio doctor` checks: speech recognition permission and the input
+/// device's format
+pub fn diagnose() -> Vec<Check> {
+    let status = unsafe { SFSpeechRecognizer::authorizationStatus() };
+    let authorization = match status.0 {
+        3 => Ok("authorized".to_string()),
+        0 => Err(anyhow!("Not granted yet; claudio asks on its first run")),
+        _ => Err(anyhow!(
+            "Denied; enable it in System Settings > Privacy & Security > Speech Recognition"
+        )),
+    };
+
+    let input = unsafe {
+        let engine = AVAudioEngine::new();
+        let format = engine.inputNode().outputFormatForBus(0);
+        if format.sampleRate() > 0.0 {
+            Ok(format!(
+                "{} Hz, {} channels",
+                format.sampleRate(),
+                format.channelCount()
+            ))
+        } else {
+            Err(anyhow!("No input device available"))
+        }
+    };
+
+    vec![
+        Check::new("authorization", authorization),
+        Check::new("input device", input),
+    ]
+}
+
 impl Drop for SpeechRecognizerImpl {
     fn drop(&mut self) {
         self.stop();
     }
 }
+
+/// Install `tap_block` on the input node in the input's current format,
+/// replacing an earlier tap, and note the format's sample rate
+unsafe fn install_tap(engine: &AVAudioEngine, tap_block: &TapBlock, sample_rate: &AtomicU32) {
+    let input_node = engine.inputNode();
+    input_node.removeTapOnBus(0);
+    let format = input_node.outputFormatForBus(0);
+    sample_rate.store(format.sampleRate() as u32, Ordering::SeqCst);
+
+    // Convert RcBlock to raw pointer for the C API
+    let tap_block_ptr = &**tap_block as *const block2::Block<_> as *mut block2::Block<_>;
+    input_node.installTapOnBus_bufferSize_format_block(0, 1024, Some(&format), tap_block_ptr);
+}
+
+/// The buffer's first channel as i16 samples, for `--save-audio` (the input
+/// node delivers float buffers)
+unsafe fn first_channel(buffer: &AVAudioPCMBuffer) -> Vec<i16> {
+    let channels = buffer.floatChannelData();
+    if channels.is_null() {
+        return Vec::new();
+    }
+    let samples = std::slice::from_raw_parts((*channels).as_ptr(), buffer.frameLength() as usize);
+    samples.iter().map(|&s| to_i16(s)).collect()
+}
+
+/// Convert a [-1.0, 1.0] sample to i16, clamping loud input instead of
+/// wrapping (as `capture::to_i16` does; capture.rs is built with cpal, which
+/// macOS builds only have with `--features vosk` or `whisper`)
+fn to_i16(sample: f32) -> i16 {
+    (sample * 32768.0).clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
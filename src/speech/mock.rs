@@ -1,97 +1,242 @@
-//! Mock speech recognizer for platforms without native support.
+//! Mock speech recognizer, selected with `--engine mock` and used as the
+//! native engine on platforms without their own recognizer.
 //!
 //! Provides a demo implementation that simulates speech recognition
 //! for testing and development purposes.
+//!
+//! Set `CLAUDIO_MOCK_SCRIPT` to replace the demo with a deterministic script
+//! of `delay_ms:text` lines. Each line waits `delay_ms` and then replaces the
+//! whole transcription with `text`, so a step can also revise or shrink what
+//! was recognized so far:
+//!
+//! ```text
+//! 300:Hello
+//! 200:Hello wor
+//! 200:Hello world
+//! 400:Hello word
+//! ```
+//!
+//! A `|` in the text marks the end of the phrases the recognizer has
+//! finished (for `--stream`); it isn't part of the transcription. Without
+//! one, nothing is finished until the script ends.
+//!
+//! With `--push-to-talk` the script only plays while the key is held.
+//!
+//! `expect:text` lines aren't played; they give the text claudio should
+//! output at the end (several lines are joined with newlines), which
+//! `--self-check` compares against.
 
+use std::env;
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicUsize, Ordering},
     Arc, Mutex,
 };
-use std::thread;
-use std::time::Duration;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
 
-use anyhow::Result;
+use super::{publish, Activity, Completion, RecognizerOptions, SharedState};
 
-pub struct SpeechRecognizerImpl {
+pub struct MockRecognizer {
     transcription: Arc<Mutex<String>>,
     is_listening: Arc<AtomicBool>,
     is_ready: Arc<AtomicBool>,
+    completion: Arc<Completion>,
+    audio: Arc<Activity>,
+    text_changed: Arc<Activity>,
+    muted: Arc<AtomicBool>,
+    committed_len: Arc<AtomicUsize>,
     stop_signal: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+    script: Vec<ScriptStep>,
+}
+
+/// One transcription update in a mock script
+#[derive(Clone)]
+struct ScriptStep {
+    delay: Duration,
+    text: String,
+    committed_len: usize, // Bytes of `text` that are finished phrases
 }
 
-impl SpeechRecognizerImpl {
-    pub fn new(
-        transcription: Arc<Mutex<String>>,
-        is_listening: Arc<AtomicBool>,
-        is_ready: Arc<AtomicBool>,
-    ) -> Result<Self> {
+/// Marks the end of the finished phrases in a script step
+const COMMITTED_MARK: char = '|';
+
+/// Prefix of the script lines giving the expected output
+const EXPECT_PREFIX: &str = "expect:";
+
+/// How often a muted or waiting script checks whether it may continue
+const MUTED_POLL: Duration = Duration::from_millis(20);
+
+/// Words played when no script is given, one every 400ms
+const DEMO_WORDS: &[&str] = &[
+    "Hello",
+    "world,",
+    "this",
+    "is",
+    "a",
+    "demo",
+    "of",
+    "speech",
+    "recognition.",
+    "The",
+    "words",
+    "fade",
+    "in",
+    "as",
+    "they",
+    "are",
+    "transcribed...",
+];
+
+impl MockRecognizer {
+    pub fn new(shared: SharedState, options: RecognizerOptions) -> Result<Self> {
+        // The script plays regardless of language; report the requested one
+        shared.set_locale(options.languages.first().map_or("en-US", String::as_str));
+        let SharedState {
+            transcription,
+            is_listening,
+            is_ready,
+            completion,
+            audio,
+            text_changed,
+            muted,
+            committed_len,
+            ..
+        } = shared;
+        let script = match env::var("CLAUDIO_MOCK_SCRIPT") {
+            Ok(script) => Self::parse_script(&script)?,
+            Err(_) => Self::demo_script(),
+        };
         Ok(Self {
             transcription,
             is_listening,
             is_ready,
+            completion,
+            audio,
+            text_changed,
+            muted,
+            committed_len,
             stop_signal: Arc::new(AtomicBool::new(false)),
+            handle: None,
+            script,
         })
     }
 
+    /// Parse `delay_ms:text` lines, skipping blank and `expect:` lines
+    fn parse_script(script: &str) -> Result<Vec<ScriptStep>> {
+        script
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with(EXPECT_PREFIX))
+            .map(|line| {
+                let (delay, text) = line.split_once(':').ok_or_else(|| {
+                    anyhow!(
+                        "Invalid mock script line '{}': expected delay_ms:text",
+                        line
+                    )
+                })?;
+                let delay = delay.trim().parse().map_err(|_| {
+                    anyhow!("Invalid mock script line '{}': bad delay '{}'", line, delay)
+                })?;
+                let (text, committed_len) = match text.split_once(COMMITTED_MARK) {
+                    Some((finished, rest)) => (format!("{}{}", finished, rest), finished.len()),
+                    None => (text.to_string(), 0),
+                };
+                Ok(ScriptStep {
+                    delay: Duration::from_millis(delay),
+                    text,
+                    committed_len,
+                })
+            })
+            .collect()
+    }
+
+    /// The demo words, appended one at a time
+    fn demo_script() -> Vec<ScriptStep> {
+        (1..=DEMO_WORDS.len())
+            .map(|n| ScriptStep {
+                delay: Duration::from_millis(400),
+                text: DEMO_WORDS[..n].join(" "),
+                committed_len: 0,
+            })
+            .collect()
+    }
+
     pub fn start(&mut self) -> Result<()> {
+        // Never play two scripts into the same transcription
+        self.stop();
+
+        // There's no microphone to wait for
+        self.audio.mark();
         self.is_ready.store(true, Ordering::SeqCst);
         self.is_listening.store(true, Ordering::SeqCst);
         self.stop_signal.store(false, Ordering::SeqCst);
 
         let transcription = Arc::clone(&self.transcription);
+        let text_changed = Arc::clone(&self.text_changed);
         let is_listening = Arc::clone(&self.is_listening);
+        let completion = Arc::clone(&self.completion);
+        let muted = Arc::clone(&self.muted);
+        let committed_len = Arc::clone(&self.committed_len);
         let stop_signal = Arc::clone(&self.stop_signal);
+        let script = self.script.clone();
 
-        // Simulate speech recognition with demo text
-        thread::spawn(move || {
-            let demo_words = [
-                "Hello",
-                "world,",
-                "this",
-                "is",
-                "a",
-                "demo",
-                "of",
-                "speech",
-                "recognition.",
-                "The",
-                "words",
-                "fade",
-                "in",
-                "as",
-                "they",
-                "are",
-                "transcribed...",
-            ];
-
-            for word in demo_words.iter() {
-                if stop_signal.load(Ordering::SeqCst) {
-                    break;
+        // Play the script, replacing the transcription at each step. A
+        // stopped script ends without touching the shared state again, as a
+        // real backend's session does once stop() returns.
+        self.handle = Some(thread::spawn(move || {
+            let stopped = || stop_signal.load(Ordering::SeqCst);
+            for step in script {
+                while muted.load(Ordering::SeqCst) && !stopped() {
+                    thread::sleep(MUTED_POLL);
                 }
-
-                thread::sleep(Duration::from_millis(400));
-
-                if let Ok(mut trans) = transcription.lock() {
-                    if !trans.is_empty() {
-                        trans.push(' ');
-                    }
-                    trans.push_str(word);
+                let until = Instant::now() + step.delay;
+                while Instant::now() < until && !stopped() {
+                    thread::sleep(MUTED_POLL.min(until.saturating_duration_since(Instant::now())));
+                }
+                if stopped() {
+                    return;
                 }
+
+                log::trace!("Mock step: {:?}", step.text);
+                publish(&transcription, &text_changed, &step.text);
+                committed_len.store(step.committed_len, Ordering::SeqCst);
             }
 
             is_listening.store(false, Ordering::SeqCst);
-        });
+            completion.signal();
+        }));
 
         Ok(())
     }
 
+    /// Stop playing, returning once the script's thread has finished
     pub fn stop(&mut self) {
         self.stop_signal.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
         self.is_listening.store(false, Ordering::SeqCst);
     }
 }
 
-impl Drop for SpeechRecognizerImpl {
+/// The output `CLAUDIO_MOCK_SCRIPT` expects, if it has `expect:` lines
+pub fn expected_text() -> Option<String> {
+    let script = env::var("CLAUDIO_MOCK_SCRIPT").ok()?;
+    let lines: Vec<&str> = script
+        .lines()
+        .filter_map(|line| line.strip_prefix(EXPECT_PREFIX))
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// The mock only "recognizes" English, for `claudio locales`
+pub fn supported_languages() -> Vec<String> {
+    vec!["en-US".to_string()]
+}
+
+impl Drop for MockRecognizer {
     fn drop(&mut self) {
         self.stop();
     }
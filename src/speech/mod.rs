@@ -1,9 +1,14 @@
-//! Speech recognition module with platform-specific implementations.
+//! Speech recognition module with several engines, selected with `--engine`.
 //!
-//! - macOS: Native Speech framework via objc2-speech
-//! - Windows: Native Windows.Media.SpeechRecognition API
+//! The `native` engine (the default) is the platform's own recognizer:
+//! - macOS: Speech framework via objc2-speech
+//! - Windows: Windows.Media.SpeechRecognition API
 //! - Linux: Vosk offline speech recognition
-//! - Other platforms: Mock implementation for testing/development
+//! - Other platforms: the mock engine
+//!
+//! `vosk` is available on Linux, or elsewhere with the `vosk` feature;
+//! `whisper` requires the `whisper` feature. `mock` replays a demo or a
+//! script and is always available (see `mock.rs`).
 
 #[cfg(target_os = "macos")]
 mod macos;
@@ -11,21 +16,661 @@ mod macos;
 #[cfg(target_os = "windows")]
 mod windows;
 
-#[cfg(target_os = "linux")]
-mod linux;
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+mod native;
+
+#[cfg(any(target_os = "linux", feature = "vosk"))]
+mod vosk;
 
-#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
 mod mock;
 
-// Re-export the appropriate implementation as SpeechRecognizer
+#[cfg(any(target_os = "linux", feature = "vosk"))]
+mod postprocess;
+
+#[cfg(feature = "whisper")]
+mod whisper;
+
+#[cfg(any(target_os = "linux", feature = "vosk", feature = "whisper"))]
+mod capture;
+
+mod recording;
+
+use std::fmt;
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Condvar, Mutex,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+pub use recording::AudioRecording;
+
+// The platform's native implementation
 #[cfg(target_os = "macos")]
-pub use macos::SpeechRecognizerImpl as SpeechRecognizer;
+use macos::SpeechRecognizerImpl as NativeRecognizer;
 
 #[cfg(target_os = "windows")]
-pub use windows::SpeechRecognizerImpl as SpeechRecognizer;
+use windows::SpeechRecognizerImpl as NativeRecognizer;
+
+/// Recognition engine, selected with `--engine`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Engine {
+    /// The platform's own recognizer
+    #[default]
+    Native,
+    /// Vosk offline recognition (Linux, or the `vosk` feature)
+    Vosk,
+    /// whisper.cpp (requires the `whisper` feature)
+    Whisper,
+    /// Simulated recognition for testing and development
+    Mock,
+}
+
+impl Engine {
+    /// Name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::Native => "native",
+            Engine::Vosk => "vosk",
+            Engine::Whisper => "whisper",
+            Engine::Mock => "mock",
+        }
+    }
+
+    /// The concrete engine this selects on the current platform; `Native`
+    /// only remains on platforms with their own recognizer (macOS, Windows)
+    pub fn resolve(self) -> Self {
+        match self {
+            Engine::Native if cfg!(target_os = "linux") => Engine::Vosk,
+            Engine::Native if !cfg!(any(target_os = "macos", target_os = "windows")) => {
+                Engine::Mock
+            }
+            engine => engine,
+        }
+    }
+
+    /// Whether this build includes the engine
+    pub fn is_available(self) -> bool {
+        match self {
+            Engine::Native => cfg!(any(target_os = "macos", target_os = "windows")),
+            Engine::Vosk => cfg!(any(target_os = "linux", feature = "vosk")),
+            Engine::Whisper => cfg!(feature = "whisper"),
+            Engine::Mock => true,
+        }
+    }
+
+    /// Engines built into this binary (`native` only where the platform has
+    /// its own recognizer)
+    pub fn available() -> Vec<Self> {
+        [Engine::Native, Engine::Vosk, Engine::Whisper, Engine::Mock]
+            .into_iter()
+            .filter(|engine| engine.is_available())
+            .collect()
+    }
+
+    /// Whether the engine's audio passes through claudio, so `--save-audio`
+    /// can save it (Windows' recognizer reads the microphone itself)
+    pub fn records_audio(self) -> bool {
+        match self.resolve() {
+            Engine::Native => cfg!(target_os = "macos"),
+            Engine::Vosk | Engine::Whisper => true,
+            Engine::Mock => false,
+        }
+    }
+
+    /// Whether recognition runs on this machine; the native engines may
+    /// send audio to Apple's or Microsoft's servers
+    pub fn on_device(self) -> bool {
+        match self.resolve() {
+            Engine::Native => false,
+            Engine::Vosk | Engine::Whisper | Engine::Mock => true,
+        }
+    }
+
+    /// Whether `--push-to-talk` can hold back the engine's audio (Windows'
+    /// recognizer reads the microphone itself)
+    pub fn gates_audio(self) -> bool {
+        match self.resolve() {
+            Engine::Native => cfg!(target_os = "macos"),
+            Engine::Vosk | Engine::Whisper | Engine::Mock => true,
+        }
+    }
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Engine {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "native" => Ok(Engine::Native),
+            "vosk" => Ok(Engine::Vosk),
+            "whisper" => Ok(Engine::Whisper),
+            "mock" => Ok(Engine::Mock),
+            _ => Err(anyhow!(
+                "Unknown engine: {} (expected native, vosk, whisper or mock)",
+                s
+            )),
+        }
+    }
+}
+
+/// A speech recognizer for the engine chosen in `RecognizerOptions`
+pub enum SpeechRecognizer {
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    Native(native::NativeWorker),
+    #[cfg(any(target_os = "linux", feature = "vosk"))]
+    Vosk(vosk::VoskRecognizer),
+    #[cfg(feature = "whisper")]
+    Whisper(whisper::WhisperRecognizer),
+    Mock(mock::MockRecognizer),
+}
+
+impl SpeechRecognizer {
+    pub fn new(shared: SharedState, options: RecognizerOptions) -> Result<Self> {
+        let engine = options.engine.resolve();
+        log::info!("Using the {} engine", engine);
+
+        match engine {
+            #[cfg(any(target_os = "macos", target_os = "windows"))]
+            Engine::Native => Ok(Self::Native(native::NativeWorker::new(shared, options)?)),
+            #[cfg(any(target_os = "linux", feature = "vosk"))]
+            Engine::Vosk => Ok(Self::Vosk(vosk::VoskRecognizer::new(shared, options)?)),
+            #[cfg(feature = "whisper")]
+            Engine::Whisper => Ok(Self::Whisper(whisper::WhisperRecognizer::new(
+                shared, options,
+            )?)),
+            Engine::Mock => Ok(Self::Mock(mock::MockRecognizer::new(shared, options)?)),
+            #[allow(unreachable_patterns)]
+            engine => Err(unavailable(engine)),
+        }
+    }
+
+    /// The engine behind this recognizer
+    pub fn engine(&self) -> Engine {
+        match self {
+            #[cfg(any(target_os = "macos", target_os = "windows"))]
+            Self::Native(_) => Engine::Native,
+            #[cfg(any(target_os = "linux", feature = "vosk"))]
+            Self::Vosk(_) => Engine::Vosk,
+            #[cfg(feature = "whisper")]
+            Self::Whisper(_) => Engine::Whisper,
+            Self::Mock(_) => Engine::Mock,
+        }
+    }
+
+    pub fn start(&mut self) -> Result<()> {
+        match self {
+            #[cfg(any(target_os = "macos", target_os = "windows"))]
+            Self::Native(recognizer) => recognizer.start(),
+            #[cfg(any(target_os = "linux", feature = "vosk"))]
+            Self::Vosk(recognizer) => recognizer.start(),
+            #[cfg(feature = "whisper")]
+            Self::Whisper(recognizer) => recognizer.start(),
+            Self::Mock(recognizer) => recognizer.start(),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        match self {
+            #[cfg(any(target_os = "macos", target_os = "windows"))]
+            Self::Native(recognizer) => recognizer.stop(),
+            #[cfg(any(target_os = "linux", feature = "vosk"))]
+            Self::Vosk(recognizer) => recognizer.stop(),
+            #[cfg(feature = "whisper")]
+            Self::Whisper(recognizer) => recognizer.stop(),
+            Self::Mock(recognizer) => recognizer.stop(),
+        }
+    }
+}
+
+/// Languages an engine can recognize, sorted: locale tags, or model names
+/// for Vosk (whose language is chosen by the model)
+pub fn supported_languages(engine: Engine) -> Result<Vec<String>> {
+    let mut languages = match engine.resolve() {
+        #[cfg(target_os = "macos")]
+        Engine::Native => macos::supported_languages()?,
+        #[cfg(target_os = "windows")]
+        Engine::Native => windows::supported_languages()?,
+        #[cfg(any(target_os = "linux", feature = "vosk"))]
+        Engine::Vosk => vosk::supported_languages()?,
+        #[cfg(feature = "whisper")]
+        Engine::Whisper => whisper::supported_languages(),
+        Engine::Mock => mock::supported_languages(),
+        #[allow(unreachable_patterns)]
+        engine => return Err(unavailable(engine)),
+    };
+    languages.sort();
+    languages.dedup();
+    Ok(languages)
+}
+
+/// The outcome of one `claudio doctor` check
+#[derive(Clone, Debug)]
+pub struct Check {
+    /// What was checked ("model", "input device")
+    pub name: &'static str,
+    pub passed: bool,
+    /// What was found, or why the check failed
+    pub detail: String,
+}
+
+impl Check {
+    #[allow(dead_code)] // Unused when no backend adds checks of its own
+    fn new(name: &'static str, result: Result<String>) -> Self {
+        match result {
+            Ok(detail) => Self {
+                name,
+                passed: true,
+                detail,
+            },
+            Err(e) => Self {
+                name,
+                passed: false,
+                detail: e.to_string(),
+            },
+        }
+    }
+}
+
+/// Check that the engine selected in `options` can run: that it's built in,
+/// then, depending on the engine, its model, the microphone and permissions
+pub fn diagnose(options: &RecognizerOptions) -> Vec<Check> {
+    let engine = options.engine.resolve();
+    let mut checks = vec![Check {
+        name: "engine",
+        passed: engine.is_available(),
+        detail: if engine.is_available() {
+            format!(
+                "{} on {}, {}",
+                engine,
+                std::env::consts::OS,
+                if engine.on_device() {
+                    "on-device"
+                } else {
+                    "may use a server"
+                }
+            )
+        } else {
+            unavailable(engine).to_string()
+        },
+    }];
+    checks.extend(match engine {
+        #[cfg(target_os = "macos")]
+        Engine::Native => macos::diagnose(),
+        #[cfg(target_os = "windows")]
+        Engine::Native => windows::diagnose(),
+        #[cfg(any(target_os = "linux", feature = "vosk"))]
+        Engine::Vosk => vosk::diagnose(&options.languages),
+        #[cfg(feature = "whisper")]
+        Engine::Whisper => whisper::diagnose(),
+        _ => Vec::new(),
+    });
+    checks
+}
+
+/// The text the mock engine's script expects claudio to output (for
+/// `--self-check`), if it gives one
+pub fn mock_expected_text() -> Option<String> {
+    mock::expected_text()
+}
+
+/// Error for an engine left out of this build
+#[allow(dead_code)] // Unused when every engine is built in
+fn unavailable(engine: Engine) -> anyhow::Error {
+    anyhow!(
+        "This build of claudio doesn't include the {} engine (rebuild with --features {})",
+        engine,
+        engine
+    )
+}
+
+/// Attempts made at a transient initialization step before giving up
+const RETRY_ATTEMPTS: u32 = 3;
+/// Delay before the first retry; doubles on each attempt
+const RETRY_BASE_DELAY_MS: u64 = 250;
+
+/// Handles shared between a recognizer backend and the app
+#[derive(Clone, Default)]
+pub struct SharedState {
+    /// Latest transcription (replaced wholesale by the backend)
+    pub transcription: Arc<Mutex<String>>,
+    /// Audio is flowing and being recognized
+    pub is_listening: Arc<AtomicBool>,
+    /// Warmup is done and it's safe to start speaking
+    pub is_ready: Arc<AtomicBool>,
+    /// Initialization failed transiently and is being retried
+    pub is_reconnecting: Arc<AtomicBool>,
+    /// The backend is loading its model (Vosk, Whisper), which takes seconds
+    /// for large ones
+    pub is_loading_model: Arc<AtomicBool>,
+    /// Recognition error reported by the backend (e.g. network loss, model failure)
+    pub error: Arc<Mutex<Option<String>>>,
+    /// Raised when recognition ends (final result, completion or error)
+    pub completion: Arc<Completion>,
+    /// When the backend last received audio from the microphone
+    pub audio: Arc<Activity>,
+    /// When the input device last changed mid-session and the backend
+    /// reconnected to it (macOS)
+    pub device_changed: Arc<Activity>,
+    /// When the backend last dropped captured audio because recognition
+    /// couldn't keep up (Vosk, Whisper)
+    pub audio_dropped: Arc<Activity>,
+    /// When the backend last changed the transcription (for `--stats`)
+    pub text_changed: Arc<Activity>,
+    /// WAV file the captured audio is saved to (`--save-audio`)
+    pub recording: Arc<AudioRecording>,
+    /// Captured audio isn't passed to the recognizer (`--push-to-talk` while
+    /// the key is up); backends end the current phrase when it's set
+    pub muted: Arc<AtomicBool>,
+    /// Language the backend settled on (a Vosk model's name), once known
+    pub locale: Arc<Mutex<Option<String>>>,
+    /// Bytes at the start of the transcription the backend won't revise
+    /// (finished phrases); stored after the text is published
+    pub committed_len: Arc<AtomicUsize>,
+}
+
+impl SharedState {
+    /// Report the language the backend recognizes
+    pub fn set_locale(&self, locale: impl Into<String>) {
+        let locale = locale.into();
+        log::debug!("Recognizing {}", locale);
+        *self.locale.lock().unwrap() = Some(locale);
+    }
+
+    /// Record a recognition error for the UI and stop listening
+    pub fn report_error(&self, message: impl Into<String>) {
+        let message = message.into();
+        log::error!("{}", message);
+        if let Ok(mut error) = self.error.lock() {
+            *error = Some(message);
+        }
+        self.is_listening.store(false, Ordering::SeqCst);
+        self.completion.signal();
+    }
+}
+
+/// Callback run when recognition ends
+type Wake = Box<dyn Fn() + Send + Sync>;
+
+/// One-shot signal that lets waiters react to the end of recognition
+/// immediately instead of on their next poll
+#[derive(Default)]
+pub struct Completion {
+    done: Mutex<bool>,
+    condvar: Condvar,
+    wakers: Mutex<Vec<Wake>>,
+}
+
+impl Completion {
+    /// Mark recognition as ended and wake all waiters
+    pub fn signal(&self) {
+        *self.done.lock().unwrap() = true;
+        self.condvar.notify_all();
+        for wake in self.wakers.lock().unwrap().iter() {
+            wake();
+        }
+    }
+
+    /// Also call `wake` every time recognition ends, for a waiter blocked on
+    /// something else (e.g. the terminal UI waiting for a key)
+    pub fn on_signal(&self, wake: impl Fn() + Send + Sync + 'static) {
+        self.wakers.lock().unwrap().push(Box::new(wake));
+    }
+
+    /// Re-arm for a new recognition session
+    pub fn reset(&self) {
+        *self.done.lock().unwrap() = false;
+    }
+
+    /// Whether recognition has ended
+    pub fn is_done(&self) -> bool {
+        *self.done.lock().unwrap()
+    }
+
+    /// Block until recognition ends or `timeout` elapses; returns whether it ended
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let done = self.done.lock().unwrap();
+        let (done, _) = self
+            .condvar
+            .wait_timeout_while(done, timeout, |done| !*done)
+            .unwrap();
+        *done
+    }
+}
+
+/// Times of the first and latest event of some kind (audio buffers arriving,
+/// transcription updates), so a muted or missing microphone can be told apart
+/// from a recognizer that is still starting
+#[derive(Default)]
+pub struct Activity {
+    times: Mutex<Option<(Instant, Instant)>>, // (first, last)
+}
+
+impl Activity {
+    /// Record that the event happened
+    pub fn mark(&self) {
+        let now = Instant::now();
+        let mut times = self.times.lock().unwrap();
+        let first = times.map_or(now, |(first, _)| first);
+        *times = Some((first, now));
+    }
+
+    /// Forget earlier events before a new recognition session
+    pub fn reset(&self) {
+        *self.times.lock().unwrap() = None;
+    }
+
+    /// When the event first happened this session, if it has
+    pub fn first(&self) -> Option<Instant> {
+        self.times.lock().unwrap().map(|(first, _)| first)
+    }
+
+    /// When the event last happened this session, if it has
+    pub fn last(&self) -> Option<Instant> {
+        self.times.lock().unwrap().map(|(_, last)| last)
+    }
+}
+
+/// Replace the transcription with `text`, noting the time if it changed
+pub(crate) fn publish(transcription: &Mutex<String>, text_changed: &Activity, text: &str) {
+    if let Ok(mut trans) = transcription.lock() {
+        if *trans != text {
+            trans.clear();
+            trans.push_str(text);
+            text_changed.mark();
+        }
+    }
+}
+
+/// Run a fallible initialization step, retrying with exponential backoff
+/// (e.g. the mic is briefly held by another app right after login).
+/// `is_reconnecting` is set while waiting so the UI can show it.
+#[allow(dead_code)] // Unused by the mock backend
+pub(crate) fn retry<T>(
+    is_reconnecting: &AtomicBool,
+    mut step: impl FnMut() -> Result<T>,
+) -> Result<T> {
+    let mut delay = Duration::from_millis(RETRY_BASE_DELAY_MS);
+    for attempt in 1..RETRY_ATTEMPTS {
+        match step() {
+            Ok(value) => {
+                is_reconnecting.store(false, Ordering::SeqCst);
+                return Ok(value);
+            }
+            Err(e) => {
+                log::warn!(
+                    "Attempt {}/{} failed, retrying in {:?}: {}",
+                    attempt,
+                    RETRY_ATTEMPTS,
+                    delay,
+                    e
+                );
+                is_reconnecting.store(true, Ordering::SeqCst);
+                thread::sleep(delay);
+                delay *= 2;
+            }
+        }
+    }
+
+    let result = step();
+    is_reconnecting.store(false, Ordering::SeqCst);
+    result
+}
+
+/// Default audio warmup before reporting ready (ms)
+pub const DEFAULT_WARMUP_MS: u64 = 200;
+
+/// Microphone gain (`--gain`), applied by the cpal-based backends
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gain {
+    /// Multiply samples by this factor (1.0 leaves them untouched)
+    Fixed(f32),
+    /// Scale quiet input up toward a target level, following recent peaks
+    Auto,
+}
+
+impl Default for Gain {
+    fn default() -> Self {
+        Gain::Fixed(1.0)
+    }
+}
+
+impl FromStr for Gain {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s == "auto" {
+            return Ok(Gain::Auto);
+        }
+        match s.parse::<f32>() {
+            Ok(factor) if factor.is_finite() && factor > 0.0 => Ok(Gain::Fixed(factor)),
+            _ => Err(anyhow!(
+                "Invalid gain: {} (expected a positive factor or \"auto\")",
+                s
+            )),
+        }
+    }
+}
+
+/// Kind of utterance expected (`--task`), a hint to the macOS recognizer
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TaskHint {
+    /// Let the recognizer decide
+    #[default]
+    Unspecified,
+    /// Free-form text, like a message or a prompt
+    Dictation,
+    /// Short search queries
+    Search,
+    /// Short commands such as "yes", "cancel" or "send it"
+    Confirmation,
+}
+
+impl FromStr for TaskHint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "dictation" => Ok(TaskHint::Dictation),
+            "search" => Ok(TaskHint::Search),
+            "confirmation" => Ok(TaskHint::Confirmation),
+            _ => Err(anyhow!(
+                "Invalid task: {} (expected dictation, search or confirmation)",
+                s
+            )),
+        }
+    }
+}
+
+/// Options passed to the recognizer backend at construction time
+#[derive(Clone, Debug)]
+pub struct RecognizerOptions {
+    /// Capitalize and punctuate raw recognizer output (Vosk only)
+    pub autopunct: bool,
+    /// Time from the first audio buffer until recognition is reported ready (macOS only)
+    pub warmup_ms: u64,
+    /// Recognition engine
+    pub engine: Engine,
+    /// Recognition languages as BCP-47 tags (e.g. "de-DE") in order of
+    /// preference; the first one available is used. System default if empty.
+    /// For Vosk they select a model by name.
+    pub languages: Vec<String>,
+    /// Pause that ends a phrase (Vosk and Windows); the engine's own default
+    /// if unset
+    pub endpoint_silence: Option<Duration>,
+    /// Input gain (Vosk and Whisper)
+    pub gain: Gain,
+    /// Only report finished phrases, not partial hypotheses (`--no-partial`)
+    pub final_only: bool,
+    /// Kind of utterance expected (macOS only)
+    pub task_hint: TaskHint,
+    /// Words and phrases to favor, such as names or project jargon (macOS
+    /// and Whisper)
+    pub vocabulary: Vec<String>,
+    /// The only phrases to recognize, for voice commands: Windows and Vosk
+    /// recognize nothing else, macOS and Whisper favor them
+    pub grammar: Vec<String>,
+}
+
+impl Default for RecognizerOptions {
+    fn default() -> Self {
+        Self {
+            autopunct: false,
+            warmup_ms: DEFAULT_WARMUP_MS,
+            engine: Engine::default(),
+            languages: Vec::new(),
+            endpoint_silence: None,
+            gain: Gain::default(),
+            final_only: false,
+            task_hint: TaskHint::default(),
+            vocabulary: Vec::new(),
+            grammar: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Restarting tears the old session down before the next one starts
+    /// (as `App::start_listening` does), so however quickly it's repeated
+    /// nothing of an earlier session keeps running once the last is stopped
+    #[test]
+    fn rapid_restarts_leave_nothing_running() {
+        let shared = SharedState::default();
+        let options = RecognizerOptions {
+            engine: Engine::Mock,
+            ..RecognizerOptions::default()
+        };
 
-#[cfg(target_os = "linux")]
-pub use linux::SpeechRecognizerImpl as SpeechRecognizer;
+        let mut recognizer: Option<SpeechRecognizer> = None;
+        for _ in 0..3 {
+            if let Some(mut previous) = recognizer.take() {
+                previous.stop();
+            }
+            shared.completion.reset();
+            let mut next = SpeechRecognizer::new(shared.clone(), options.clone()).unwrap();
+            next.start().unwrap();
+            recognizer = Some(next);
+        }
+        recognizer.unwrap().stop();
 
-#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
-pub use mock::SpeechRecognizerImpl as SpeechRecognizer;
+        // Longer than a step of the demo script
+        shared.transcription.lock().unwrap().clear();
+        thread::sleep(Duration::from_millis(600));
+        assert!(shared.transcription.lock().unwrap().is_empty());
+        assert!(!shared.is_listening.load(Ordering::SeqCst));
+        assert!(!shared.completion.is_done());
+    }
+}
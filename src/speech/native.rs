@@ -0,0 +1,77 @@
+//! The platform's recognizer (macOS, Windows) on a thread of its own
+//!
+//! Creating and starting it can wait on retries (the microphone briefly held
+//! by another app, the speech service still starting up). That happens on
+//! this thread, the way the Vosk and Whisper workers load their models and
+//! open the microphone, so the UI keeps drawing and shows "Reconnecting..."
+//! meanwhile. The backend's objects stay on the thread for the whole
+//! session; startup errors are reported through `SharedState::report_error`.
+
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+use anyhow::Result;
+
+use super::{NativeRecognizer, RecognizerOptions, SharedState};
+
+pub struct NativeWorker {
+    shared: SharedState,
+    options: RecognizerOptions,
+    // Dropped by stop() to end the session
+    stop_tx: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl NativeWorker {
+    pub fn new(shared: SharedState, options: RecognizerOptions) -> Result<Self> {
+        Ok(Self {
+            shared,
+            options,
+            stop_tx: None,
+            handle: None,
+        })
+    }
+
+    pub fn start(&mut self) -> Result<()> {
+        // Never run two sessions at once
+        self.stop();
+
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let shared = self.shared.clone();
+        let options = self.options.clone();
+        let handle = thread::spawn(move || {
+            let started =
+                NativeRecognizer::new(shared.clone(), options).and_then(|mut recognizer| {
+                    recognizer.start()?;
+                    Ok(recognizer)
+                });
+            match started {
+                Ok(mut recognizer) => {
+                    // Returns once stop() drops the sender
+                    let _ = stop_rx.recv();
+                    recognizer.stop();
+                }
+                Err(e) => shared.report_error(format!("Failed to start speech recognition: {}", e)),
+            }
+        });
+
+        self.stop_tx = Some(stop_tx);
+        self.handle = Some(handle);
+        Ok(())
+    }
+
+    /// End the session, returning once the backend has released the
+    /// microphone
+    pub fn stop(&mut self) {
+        self.stop_tx = None;
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for NativeWorker {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,146 @@
+//! Text post-processing for recognizers that emit raw, unformatted output.
+//!
+//! Vosk returns lowercase text without punctuation, unlike the formatted
+//! strings produced by the macOS and Windows engines. These helpers restore
+//! basic sentence structure using the word timestamps Vosk provides.
+
+/// Pause between words (in seconds) treated as a sentence boundary
+const SENTENCE_PAUSE_SECS: f32 = 0.8;
+
+/// A recognized word with its timing in the audio stream (seconds)
+pub struct TimedWord<'a> {
+    pub text: &'a str,
+    pub start: f32,
+    pub end: f32,
+}
+
+/// Capitalize sentence starts and the pronoun "I", and insert periods at long
+/// pauses. When `finished` is true the text is also terminated with a period.
+pub fn punctuate(words: &[TimedWord], finished: bool) -> String {
+    let mut out = String::new();
+    let mut sentence_start = true;
+    let mut prev_end: Option<f32> = None;
+
+    for word in words {
+        if word.text.is_empty() {
+            continue;
+        }
+
+        if let Some(end) = prev_end {
+            if word.start - end >= SENTENCE_PAUSE_SECS && !sentence_start {
+                end_sentence(&mut out);
+                sentence_start = true;
+            }
+            out.push(' ');
+        }
+
+        if sentence_start || is_pronoun_i(word.text) {
+            out.push_str(&capitalize(word.text));
+        } else {
+            out.push_str(word.text);
+        }
+
+        sentence_start = false;
+        prev_end = Some(word.end);
+    }
+
+    if finished && !out.is_empty() {
+        end_sentence(&mut out);
+    }
+
+    out
+}
+
+/// Same as [`punctuate`] for text without timestamps (no pause detection)
+pub fn punctuate_text(text: &str, finished: bool) -> String {
+    let words: Vec<TimedWord> = text
+        .split_whitespace()
+        .map(|w| TimedWord {
+            text: w,
+            start: 0.0,
+            end: 0.0,
+        })
+        .collect();
+    punctuate(&words, finished)
+}
+
+fn end_sentence(out: &mut String) {
+    if !out.ends_with(['.', '?', '!']) {
+        out.push('.');
+    }
+}
+
+/// "i", "i'm", "i'll", "i've", "i'd"
+fn is_pronoun_i(word: &str) -> bool {
+    word == "i" || word.starts_with("i'")
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Words with the given (start, end) times
+    fn timed<'a>(words: &[(&'a str, f32, f32)]) -> Vec<TimedWord<'a>> {
+        words
+            .iter()
+            .map(|&(text, start, end)| TimedWord { text, start, end })
+            .collect()
+    }
+
+    #[test]
+    fn long_pause_ends_a_sentence() {
+        let words = timed(&[
+            ("hello", 0.0, 0.4),
+            ("there", 0.5, 0.9),
+            ("how", 1.7, 1.9), // 0.8s after "there"
+            ("are", 2.0, 2.1),
+            ("you", 2.8, 3.0), // 0.7s after "are"
+        ]);
+        assert_eq!(punctuate(&words, false), "Hello there. How are you");
+    }
+
+    #[test]
+    fn existing_punctuation_isnt_doubled() {
+        let words = timed(&[("really?", 0.0, 0.5), ("yes", 2.0, 2.2), ("wow!", 2.3, 2.6)]);
+        assert_eq!(punctuate(&words, true), "Really? Yes wow!");
+    }
+
+    #[test]
+    fn pronoun_i_is_capitalized() {
+        assert_eq!(
+            punctuate_text("so i think i'm done and i'll go but it's fine", false),
+            "So I think I'm done and I'll go but it's fine"
+        );
+        // Not words that merely start with "i"
+        assert_eq!(punctuate_text("it is in ink", false), "It is in ink");
+    }
+
+    #[test]
+    fn empty_words_are_skipped() {
+        let words = timed(&[
+            ("", 0.0, 0.0),
+            ("hi", 0.1, 0.3),
+            ("", 2.0, 2.0),
+            ("you", 0.4, 0.6),
+        ]);
+        assert_eq!(punctuate(&words, false), "Hi you");
+        assert_eq!(punctuate(&timed(&[("", 0.0, 0.0)]), true), "");
+    }
+
+    #[test]
+    fn finished_text_ends_with_a_period() {
+        assert_eq!(punctuate_text("see you soon", true), "See you soon.");
+        assert_eq!(punctuate_text("see you soon", false), "See you soon");
+        assert_eq!(punctuate_text("", true), "");
+        // Text without timestamps never gets pause periods
+        assert_eq!(punctuate_text("one two three", false), "One two three");
+    }
+}
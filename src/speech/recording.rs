@@ -0,0 +1,148 @@
+//! Saving the captured microphone audio to a WAV file (`--save-audio`)
+//!
+//! Audio is written as 16-bit mono PCM at the rate the backend captures at.
+//! The header is rewritten at most every `HEADER_INTERVAL`, when a session
+//! stops and when the file is closed, so a process killed mid-recording
+//! leaves a playable file missing at most the last moments. Sessions
+//! restarted after an edit or a pause append to the same file.
+
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+
+/// Size of the RIFF/WAVE header written before the samples
+const HEADER_LEN: u32 = 44;
+
+/// How often the header is brought up to date while audio is written
+const HEADER_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The WAV file audio is being saved to, if any
+#[derive(Default)]
+pub struct AudioRecording {
+    writer: Mutex<Option<WavWriter>>,
+}
+
+impl AudioRecording {
+    /// Create (or truncate) the file that captured audio is saved to
+    pub fn create(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)
+            .map_err(|e| anyhow!("Failed to create {}: {}", path.display(), e))?;
+        let mut writer = WavWriter {
+            file,
+            sample_rate: None,
+            data_len: 0,
+            header_written: Instant::now(),
+        };
+        writer
+            .write_header()
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+        *self.writer.lock().unwrap() = Some(writer);
+        Ok(())
+    }
+
+    /// Whether audio is being saved, so backends can skip converting it
+    #[allow(dead_code)] // Only the macOS backend converts
+    pub fn is_active(&self) -> bool {
+        self.writer.lock().unwrap().is_some()
+    }
+
+    /// Append mono samples captured at `sample_rate`. A write error stops the
+    /// recording (logged) rather than recognition.
+    pub fn write(&self, samples: &[i16], sample_rate: u32) {
+        if samples.is_empty() {
+            return;
+        }
+        let Ok(mut guard) = self.writer.lock() else {
+            return;
+        };
+        let Some(writer) = guard.as_mut() else {
+            return;
+        };
+        if let Err(e) = writer.write(samples, sample_rate) {
+            log::error!("Failed to save audio, recording stopped: {}", e);
+            *guard = None;
+        }
+    }
+
+    /// Bring the header up to date with the audio written so far (a session
+    /// stopped)
+    pub fn flush(&self) {
+        let Ok(mut guard) = self.writer.lock() else {
+            return;
+        };
+        if let Some(writer) = guard.as_mut() {
+            if let Err(e) = writer.write_header() {
+                log::error!("Failed to save audio, recording stopped: {}", e);
+                *guard = None;
+            }
+        }
+    }
+}
+
+struct WavWriter {
+    file: File,
+    sample_rate: Option<u32>, // Set by the first buffer
+    data_len: u32,            // Bytes of samples written so far
+    header_written: Instant,
+}
+
+impl WavWriter {
+    fn write(&mut self, samples: &[i16], sample_rate: u32) -> std::io::Result<()> {
+        match self.sample_rate {
+            None => self.sample_rate = Some(sample_rate),
+            Some(rate) if rate != sample_rate => {
+                log::warn!(
+                    "Input sample rate changed from {} to {} Hz; saved audio will play at the wrong speed",
+                    rate,
+                    sample_rate
+                );
+                self.sample_rate = Some(sample_rate);
+            }
+            Some(_) => {}
+        }
+
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        self.file.seek(SeekFrom::End(0))?;
+        self.file.write_all(&bytes)?;
+        self.data_len = self.data_len.saturating_add(bytes.len() as u32);
+        if self.header_written.elapsed() >= HEADER_INTERVAL {
+            self.write_header()?;
+        }
+        Ok(())
+    }
+
+    /// Write the header for the samples written so far
+    fn write_header(&mut self) -> std::io::Result<()> {
+        // Used for an empty file, when no audio arrived
+        let sample_rate = self.sample_rate.unwrap_or(16000);
+        let mut header = Vec::with_capacity(HEADER_LEN as usize);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&(HEADER_LEN - 8).saturating_add(self.data_len).to_le_bytes());
+        header.extend_from_slice(b"WAVE");
+        header.extend_from_slice(b"fmt ");
+        header.extend_from_slice(&16u32.to_le_bytes()); // fmt chunk size
+        header.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        header.extend_from_slice(&1u16.to_le_bytes()); // Mono
+        header.extend_from_slice(&sample_rate.to_le_bytes());
+        header.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // Byte rate
+        header.extend_from_slice(&2u16.to_le_bytes()); // Block align
+        header.extend_from_slice(&16u16.to_le_bytes()); // Bits per sample
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&self.data_len.to_le_bytes());
+
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header)?;
+        self.header_written = Instant::now();
+        Ok(())
+    }
+}
+
+impl Drop for WavWriter {
+    fn drop(&mut self) {
+        let _ = self.write_header();
+    }
+}
@@ -0,0 +1,386 @@
+//! Vosk offline speech recognition (the native engine on Linux).
+//!
+//! Requires a Vosk model to be downloaded and available. The model path
+//! can be configured via:
+//! 1. `VOSK_MODEL_PATH` environment variable
+//! 2. `~/.local/share/vosk/model` (default)
+//!
+//! With `--lang`, the first language that names one of the models next to
+//! that path (e.g. `de` for `vosk-model-small-de-0.15`) picks it instead.
+//!
+//! Download models from: https://alphacephei.com/vosk/models
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use vosk::{CompleteResult, DecodingState, Model, Recognizer, Word};
+
+use super::capture::{self, AudioQueue};
+use super::postprocess::{self, TimedWord};
+use super::{publish, retry, Check, RecognizerOptions, SharedState};
+
+/// Audio kept while Vosk falls behind; older samples are dropped beyond this
+const MAX_BUFFERED_SECS: usize = 5;
+/// Longest the processing thread waits for audio before rechecking for stop
+const AUDIO_WAIT_MS: u64 = 100;
+
+pub struct VoskRecognizer {
+    shared: SharedState,
+    stop_signal: Arc<AtomicBool>,
+    stream_handle: Option<thread::JoinHandle<()>>,
+    options: RecognizerOptions,
+}
+
+impl VoskRecognizer {
+    pub fn new(shared: SharedState, options: RecognizerOptions) -> Result<Self> {
+        // Verify model exists at startup
+        let model_path = Self::get_model_path(&options.languages)?;
+        if !model_path.exists() {
+            return Err(anyhow!(
+                "Vosk model not found at: {}\n\
+                 Download a model from https://alphacephei.com/vosk/models\n\
+                 and extract it to ~/.local/share/vosk/model\n\
+                 or set VOSK_MODEL_PATH environment variable",
+                model_path.display()
+            ));
+        }
+
+        Ok(Self {
+            shared,
+            stop_signal: Arc::new(AtomicBool::new(false)),
+            stream_handle: None,
+            options,
+        })
+    }
+
+    /// The model for the first of `languages` that has one, else the
+    /// configured model
+    fn get_model_path(languages: &[String]) -> Result<PathBuf> {
+        let configured = Self::configured_model_path()?;
+        if languages.is_empty() {
+            return Ok(configured);
+        }
+
+        let names = model_names(&configured);
+        for tag in languages {
+            if let Some(name) = names.iter().find(|name| model_matches(name, tag)) {
+                log::info!("Using Vosk model {} for language {}", name, tag);
+                return Ok(configured.with_file_name(name));
+            }
+            log::debug!("No Vosk model for language {}", tag);
+        }
+        log::warn!(
+            "No Vosk model for {}, using {}",
+            languages.join(", "),
+            configured.display()
+        );
+        Ok(configured)
+    }
+
+    fn configured_model_path() -> Result<PathBuf> {
+        // Check environment variable first
+        if let Ok(path) = env::var("VOSK_MODEL_PATH") {
+            return Ok(PathBuf::from(path));
+        }
+
+        // Default to ~/.local/share/vosk/model
+        let home = env::var("HOME").map_err(|_| anyhow!("HOME environment variable not set"))?;
+        Ok(PathBuf::from(home).join(".local/share/vosk/model"))
+    }
+
+    pub fn start(&mut self) -> Result<()> {
+        self.stop_signal.store(false, Ordering::SeqCst);
+
+        let model_path = Self::get_model_path(&self.options.languages)?;
+        if let Some(name) = model_path.file_name() {
+            self.shared.set_locale(name.to_string_lossy());
+        }
+        let shared = self.shared.clone();
+        let stop_signal = Arc::clone(&self.stop_signal);
+        let options = self.options.clone();
+
+        // Spawn audio capture thread
+        let handle = thread::spawn(move || {
+            let error_state = shared.clone();
+            if let Err(e) = Self::run_recognition(model_path, shared, stop_signal, options) {
+                error_state.report_error(format!("Speech recognition error: {}", e));
+            }
+        });
+
+        self.stream_handle = Some(handle);
+        Ok(())
+    }
+
+    fn run_recognition(
+        model_path: PathBuf,
+        shared: SharedState,
+        stop_signal: Arc<AtomicBool>,
+        options: RecognizerOptions,
+    ) -> Result<()> {
+        let SharedState {
+            transcription,
+            is_listening,
+            is_ready,
+            is_reconnecting,
+            is_loading_model,
+            completion,
+            audio,
+            audio_dropped,
+            recording,
+            text_changed,
+            muted,
+            committed_len,
+            ..
+        } = shared;
+
+        // Load the Vosk model
+        log::debug!("Loading Vosk model from {}", model_path.display());
+        is_loading_model.store(true, Ordering::SeqCst);
+        let model = Model::new(model_path.to_string_lossy());
+        is_loading_model.store(false, Ordering::SeqCst);
+        let model = model
+            .ok_or_else(|| anyhow!("Failed to load Vosk model from {}", model_path.display()))?;
+
+        // Set up audio capture (the device may be briefly busy, so retry)
+        let audio_queue = Arc::new(AudioQueue::new(
+            capture::SAMPLE_RATE as usize * MAX_BUFFERED_SECS,
+        ));
+        let _stream = retry(&is_reconnecting, || {
+            capture::open_input(
+                Arc::clone(&audio_queue),
+                Arc::clone(&audio),
+                Arc::clone(&muted),
+                options.gain,
+            )
+        })?;
+        let sample_rate = capture::SAMPLE_RATE as f32;
+
+        // Create recognizer with the sample rate. With --grammar it only
+        // knows the phrases (in lowercase, like the model's words) and "[unk]"
+        // for anything else.
+        let recognizer = if options.grammar.is_empty() {
+            Recognizer::new(&model, sample_rate)
+        } else {
+            let mut grammar: Vec<String> = options
+                .grammar
+                .iter()
+                .map(|phrase| phrase.to_lowercase())
+                .collect();
+            grammar.push("[unk]".to_string());
+            Recognizer::new_with_grammar(&model, sample_rate, &grammar)
+        };
+        let mut recognizer =
+            recognizer.ok_or_else(|| anyhow!("Failed to create Vosk recognizer"))?;
+
+        recognizer.set_words(true);
+        recognizer.set_partial_words(true);
+
+        is_ready.store(true, Ordering::SeqCst);
+        is_listening.store(true, Ordering::SeqCst);
+        log::info!("Recognizer ready ({} Hz)", sample_rate);
+
+        // Phrases Vosk has finalized; the transcription is these plus the
+        // current partial result
+        let mut committed = String::new();
+        let mut partial_text = String::new();
+        let mut last_change = Instant::now();
+
+        // Process audio in a loop
+        while !stop_signal.load(Ordering::SeqCst) {
+            // Wait for accumulated samples (woken as soon as audio arrives)
+            let samples = audio_queue.take(Duration::from_millis(AUDIO_WAIT_MS));
+            recording.write(&samples, capture::SAMPLE_RATE);
+
+            let dropped = audio_queue.take_dropped();
+            if dropped > 0 {
+                audio_dropped.mark();
+                log::warn!(
+                    "Recognizer falling behind, dropped {:.1}s of audio",
+                    dropped as f32 / sample_rate
+                );
+            }
+
+            if !samples.is_empty() {
+                log::trace!("Feeding {} samples", samples.len());
+                // Feed to recognizer
+                let state = recognizer.accept_waveform(&samples);
+
+                if matches!(state, Ok(DecodingState::Finalized)) {
+                    // Vosk detected the end of a phrase
+                    let phrase = Self::phrase_text(recognizer.result(), options.autopunct);
+                    committed = join_text(&committed, &phrase);
+                    partial_text.clear();
+                } else {
+                    // Get partial result for real-time feedback
+                    let partial = recognizer.partial_result();
+                    let text = if options.autopunct {
+                        Self::punctuate(partial.partial, &partial.partial_result, false)
+                    } else {
+                        partial.partial.to_string()
+                    };
+                    if text != partial_text {
+                        partial_text = text;
+                        last_change = Instant::now();
+                    }
+                }
+                // --no-partial: the partial result still tracks pauses for
+                // --endpoint-silence-ms, but isn't shown
+                if options.final_only {
+                    publish(&transcription, &text_changed, &committed);
+                } else {
+                    publish(
+                        &transcription,
+                        &text_changed,
+                        &join_text(&committed, &partial_text),
+                    );
+                }
+                committed_len.store(committed.len(), Ordering::SeqCst);
+            }
+
+            // --endpoint-silence-ms: end the phrase ourselves once no new
+            // words have arrived for that long. --push-to-talk: releasing the
+            // key ends it right away.
+            let silent = options
+                .endpoint_silence
+                .is_some_and(|silence| last_change.elapsed() >= silence);
+            let released = muted.load(Ordering::SeqCst);
+            if !partial_text.is_empty() && (silent || released) {
+                log::debug!(
+                    "Ending phrase {}",
+                    if released {
+                        "on key release"
+                    } else {
+                        "after a pause without new words"
+                    }
+                );
+                let phrase = Self::phrase_text(recognizer.final_result(), options.autopunct);
+                committed = join_text(&committed, &phrase);
+                partial_text.clear();
+                publish(&transcription, &text_changed, &committed);
+                committed_len.store(committed.len(), Ordering::SeqCst);
+            }
+        }
+
+        // Get final result
+        let phrase = Self::phrase_text(recognizer.final_result(), options.autopunct);
+        if !phrase.is_empty() || !committed.is_empty() {
+            publish(
+                &transcription,
+                &text_changed,
+                &join_text(&committed, &phrase),
+            );
+        }
+
+        is_listening.store(false, Ordering::SeqCst);
+        completion.signal();
+        log::info!("Recognition stopped");
+        Ok(())
+    }
+
+    /// Text of a finished phrase, punctuated with `--autopunct`
+    fn phrase_text(result: CompleteResult, autopunct: bool) -> String {
+        let Some(result) = result.single() else {
+            return String::new();
+        };
+        if autopunct && !result.text.is_empty() {
+            Self::punctuate(result.text, &result.result, true)
+        } else {
+            result.text.to_string()
+        }
+    }
+
+    /// Apply auto-punctuation, using word timestamps when Vosk provides them
+    fn punctuate(text: &str, words: &[Word], finished: bool) -> String {
+        if words.is_empty() {
+            return postprocess::punctuate_text(text, finished);
+        }
+        let timed: Vec<TimedWord> = words
+            .iter()
+            .map(|w| TimedWord {
+                text: w.word,
+                start: w.start,
+                end: w.end,
+            })
+            .collect();
+        postprocess::punctuate(&timed, finished)
+    }
+
+    pub fn stop(&mut self) {
+        self.stop_signal.store(true, Ordering::SeqCst);
+        self.shared.is_listening.store(false, Ordering::SeqCst);
+
+        // Wait for the thread to finish
+        if let Some(handle) = self.stream_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Models in the directory of the configured one (e.g. ~/.local/share/vosk),
+/// for `claudio locales`. Vosk's language comes from the model, so model
+/// names stand in for locales.
+pub fn supported_languages() -> Result<Vec<String>> {
+    Ok(model_names(&VoskRecognizer::configured_model_path()?))
+}
+
+/// `claudio doctor` checks: the model `--lang` selects and the microphone
+pub fn diagnose(languages: &[String]) -> Vec<Check> {
+    let model = VoskRecognizer::get_model_path(languages).and_then(|path| {
+        if path.exists() {
+            Ok(path.display().to_string())
+        } else {
+            Err(anyhow!(
+                "Not found at {} (download one from https://alphacephei.com/vosk/models \
+                 or set VOSK_MODEL_PATH)",
+                path.display()
+            ))
+        }
+    });
+    vec![
+        Check::new("model", model),
+        Check::new("input device", capture::describe_input()),
+    ]
+}
+
+/// Names of the model directories next to `model_path` (including its own)
+fn model_names(model_path: &Path) -> Vec<String> {
+    let Some(Ok(entries)) = model_path.parent().map(fs::read_dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.path().join("am").is_dir() || entry.path().join("conf").is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect()
+}
+
+/// Whether the model called `name` is for the language `tag`: the full name,
+/// or the tag as a part of it ("en-US" matches "vosk-model-en-us-0.22")
+fn model_matches(name: &str, tag: &str) -> bool {
+    let name = name.to_lowercase();
+    let tag = tag.to_lowercase().replace('_', "-");
+    name == tag || name.contains(&format!("-{}-", tag)) || name.ends_with(&format!("-{}", tag))
+}
+
+impl Drop for VoskRecognizer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Join two pieces of text with a space, skipping empty ones
+fn join_text(a: &str, b: &str) -> String {
+    match (a.is_empty(), b.is_empty()) {
+        (true, _) => b.to_string(),
+        (_, true) => a.to_string(),
+        _ => format!("{} {}", a, b),
+    }
+}
@@ -0,0 +1,345 @@
+//! Whisper speech recognition using whisper.cpp (via whisper-rs).
+//!
+//! Built with the `whisper` cargo feature and selected with `--engine whisper`.
+//! Requires a ggml model file, configured via:
+//! 1. `WHISPER_MODEL_PATH` environment variable
+//! 2. `~/.local/share/whisper/ggml-base.en.bin` (default)
+//!
+//! Download models from: https://huggingface.co/ggerganov/whisper.cpp
+//!
+//! Whisper isn't a streaming recognizer, so the current window of audio is
+//! re-transcribed about once a second. Once a window grows past
+//! `WINDOW_SECS` (or `--push-to-talk`'s key is released) its text is
+//! committed and a new window starts.
+
+use std::env;
+use std::path::PathBuf;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use whisper_rs::{
+    FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState,
+};
+
+use super::capture::{self, AudioQueue};
+use super::{publish, retry, Check, RecognizerOptions, SharedState};
+
+/// Audio kept while transcription falls behind
+const MAX_BUFFERED_SECS: usize = 5;
+/// Longest the processing thread waits for audio before rechecking for stop
+const AUDIO_WAIT_MS: u64 = 100;
+/// New audio needed before the window is transcribed again (ms)
+const STEP_MS: usize = 1000;
+/// Window length after which its text is committed (Whisper handles up to 30s)
+const WINDOW_SECS: usize = 25;
+
+pub struct WhisperRecognizer {
+    shared: SharedState,
+    stop_signal: Arc<AtomicBool>,
+    stream_handle: Option<thread::JoinHandle<()>>,
+    options: RecognizerOptions,
+}
+
+impl WhisperRecognizer {
+    pub fn new(shared: SharedState, options: RecognizerOptions) -> Result<Self> {
+        // Verify model exists at startup
+        let model_path = Self::get_model_path()?;
+        if !model_path.exists() {
+            return Err(anyhow!(
+                "Whisper model not found at: {}\n\
+                 Download a ggml model from https://huggingface.co/ggerganov/whisper.cpp\n\
+                 and save it as ~/.local/share/whisper/ggml-base.en.bin\n\
+                 or set WHISPER_MODEL_PATH environment variable",
+                model_path.display()
+            ));
+        }
+
+        Ok(Self {
+            shared,
+            stop_signal: Arc::new(AtomicBool::new(false)),
+            stream_handle: None,
+            options,
+        })
+    }
+
+    fn get_model_path() -> Result<PathBuf> {
+        // Check environment variable first
+        if let Ok(path) = env::var("WHISPER_MODEL_PATH") {
+            return Ok(PathBuf::from(path));
+        }
+
+        // Default to ~/.local/share/whisper/ggml-base.en.bin
+        let home = env::var("HOME")
+            .or_else(|_| env::var("USERPROFILE"))
+            .map_err(|_| anyhow!("HOME environment variable not set"))?;
+        Ok(PathBuf::from(home).join(".local/share/whisper/ggml-base.en.bin"))
+    }
+
+    pub fn start(&mut self) -> Result<()> {
+        self.stop_signal.store(false, Ordering::SeqCst);
+
+        let model_path = Self::get_model_path()?;
+        let shared = self.shared.clone();
+        let stop_signal = Arc::clone(&self.stop_signal);
+        let options = self.options.clone();
+
+        // Spawn transcription thread
+        let handle = thread::spawn(move || {
+            let error_state = shared.clone();
+            if let Err(e) = Self::run_recognition(model_path, shared, stop_signal, options) {
+                error_state.report_error(format!("Speech recognition error: {}", e));
+            }
+        });
+
+        self.stream_handle = Some(handle);
+        Ok(())
+    }
+
+    fn run_recognition(
+        model_path: PathBuf,
+        shared: SharedState,
+        stop_signal: Arc<AtomicBool>,
+        options: RecognizerOptions,
+    ) -> Result<()> {
+        let status = shared.clone();
+        let SharedState {
+            transcription,
+            is_listening,
+            is_ready,
+            is_reconnecting,
+            is_loading_model,
+            completion,
+            audio,
+            audio_dropped,
+            recording,
+            text_changed,
+            muted,
+            committed_len,
+            ..
+        } = shared;
+
+        // Route whisper.cpp's own output through `log` instead of stderr
+        whisper_rs::install_logging_hooks();
+
+        // Load the model
+        log::debug!("Loading Whisper model from {}", model_path.display());
+        is_loading_model.store(true, Ordering::SeqCst);
+        let context = WhisperContext::new_with_params(
+            &model_path.to_string_lossy(),
+            WhisperContextParameters::default(),
+        );
+        is_loading_model.store(false, Ordering::SeqCst);
+        let context = context.map_err(|e| {
+            anyhow!(
+                "Failed to load Whisper model from {}: {:?}",
+                model_path.display(),
+                e
+            )
+        })?;
+        let mut state = context
+            .create_state()
+            .map_err(|e| anyhow!("Failed to create Whisper state: {:?}", e))?;
+
+        // "de-DE" -> "de"; Whisper only wants the language code. The first
+        // requested language Whisper knows is used, else it detects one.
+        let known = supported_languages();
+        let language = options
+            .languages
+            .iter()
+            .map(|tag| tag.split(['-', '_']).next().unwrap_or(tag).to_lowercase())
+            .find(|code| known.contains(code))
+            .unwrap_or_else(|| {
+                if !options.languages.is_empty() {
+                    log::warn!(
+                        "Whisper doesn't know {}, detecting the language",
+                        options.languages.join(", ")
+                    );
+                }
+                "auto".to_string()
+            });
+        status.set_locale(&language);
+
+        // Whisper has no phrase list, but words in the prompt (taken as the
+        // text before the audio) are more likely to be recognized
+        let prompt = [&options.vocabulary[..], &options.grammar[..]]
+            .concat()
+            .join(", ");
+
+        // Set up audio capture (the device may be briefly busy, so retry)
+        let audio_queue = Arc::new(AudioQueue::new(
+            capture::SAMPLE_RATE as usize * MAX_BUFFERED_SECS,
+        ));
+        let _stream = retry(&is_reconnecting, || {
+            capture::open_input(
+                Arc::clone(&audio_queue),
+                Arc::clone(&audio),
+                Arc::clone(&muted),
+                options.gain,
+            )
+        })?;
+
+        is_ready.store(true, Ordering::SeqCst);
+        is_listening.store(true, Ordering::SeqCst);
+        log::info!("Recognizer ready (Whisper, language {})", language);
+
+        let step = capture::SAMPLE_RATE as usize * STEP_MS / 1000;
+        let window_len = capture::SAMPLE_RATE as usize * WINDOW_SECS;
+        let mut window: Vec<f32> = Vec::new();
+        let mut transcribed_len = 0;
+        let mut window_text = String::new(); // Text of the transcribed part
+        let mut committed = String::new();
+
+        while !stop_signal.load(Ordering::SeqCst) {
+            // Wait for accumulated samples (woken as soon as audio arrives)
+            let samples = audio_queue.take(Duration::from_millis(AUDIO_WAIT_MS));
+            recording.write(&samples, capture::SAMPLE_RATE);
+            let dropped = audio_queue.take_dropped();
+            if dropped > 0 {
+                audio_dropped.mark();
+                log::warn!(
+                    "Recognizer falling behind, dropped {:.1}s of audio",
+                    dropped as f32 / capture::SAMPLE_RATE as f32
+                );
+            }
+            window.extend(samples.iter().map(|&s| s as f32 / 32768.0));
+
+            // --push-to-talk: releasing the key commits the window
+            let released = muted.load(Ordering::SeqCst) && !window.is_empty();
+            if window.len() - transcribed_len < step && !released {
+                continue;
+            }
+
+            // --no-partial: only transcribe once the window is committed
+            if options.final_only && window.len() < window_len && !released {
+                continue;
+            }
+            if window.len() > transcribed_len {
+                window_text = Self::transcribe(&mut state, &window, &language, &prompt)?;
+                transcribed_len = window.len();
+                publish(
+                    &transcription,
+                    &text_changed,
+                    &join_text(&committed, &window_text),
+                );
+            }
+
+            if window.len() >= window_len || released {
+                committed = join_text(&committed, &window_text);
+                committed_len.store(committed.len(), Ordering::SeqCst);
+                window.clear();
+                window_text.clear();
+                transcribed_len = 0;
+            }
+        }
+
+        // Transcribe whatever arrived since the last pass
+        if window.len() > transcribed_len {
+            let text = Self::transcribe(&mut state, &window, &language, &prompt)?;
+            publish(&transcription, &text_changed, &join_text(&committed, &text));
+        }
+
+        is_listening.store(false, Ordering::SeqCst);
+        completion.signal();
+        log::info!("Recognition stopped");
+        Ok(())
+    }
+
+    /// Run Whisper over `audio` (16 kHz mono) and return the text
+    fn transcribe(
+        state: &mut WhisperState,
+        audio: &[f32],
+        language: &str,
+        prompt: &str,
+    ) -> Result<String> {
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_language(Some(language));
+        if !prompt.is_empty() {
+            params.set_initial_prompt(prompt);
+        }
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+
+        state
+            .full(params, audio)
+            .map_err(|e| anyhow!("Whisper transcription failed: {:?}", e))?;
+
+        let segments = state
+            .full_n_segments()
+            .map_err(|e| anyhow!("Whisper transcription failed: {:?}", e))?;
+        let mut text = String::new();
+        for i in 0..segments {
+            let segment = state
+                .full_get_segment_text(i)
+                .map_err(|e| anyhow!("Whisper transcription failed: {:?}", e))?;
+            let segment = segment.trim();
+            // Skip non-speech annotations like "[BLANK_AUDIO]" or "(wind blowing)"
+            if segment.is_empty()
+                || (segment.starts_with('[') && segment.ends_with(']'))
+                || (segment.starts_with('(') && segment.ends_with(')'))
+            {
+                continue;
+            }
+            text = join_text(&text, segment);
+        }
+        Ok(text)
+    }
+
+    pub fn stop(&mut self) {
+        self.stop_signal.store(true, Ordering::SeqCst);
+        self.shared.is_listening.store(false, Ordering::SeqCst);
+
+        // Wait for the thread to finish
+        if let Some(handle) = self.stream_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// `claudio doctor` checks: the model and the microphone
+pub fn diagnose() -> Vec<Check> {
+    let model = WhisperRecognizer::get_model_path().and_then(|path| {
+        if path.exists() {
+            Ok(path.display().to_string())
+        } else {
+            Err(anyhow!(
+                "Not found at {} (download a ggml model from \
+                 https://huggingface.co/ggerganov/whisper.cpp or set WHISPER_MODEL_PATH)",
+                path.display()
+            ))
+        }
+    });
+    vec![
+        Check::new("model", model),
+        Check::new("input device", capture::describe_input()),
+    ]
+}
+
+/// Language codes Whisper can recognize, for `claudio locales`
+pub fn supported_languages() -> Vec<String> {
+    (0..=whisper_rs::get_lang_max_id())
+        .filter_map(whisper_rs::get_lang_str)
+        .map(str::to_string)
+        .collect()
+}
+
+impl Drop for WhisperRecognizer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Join two pieces of text with a space, skipping empty ones
+fn join_text(a: &str, b: &str) -> String {
+    match (a.is_empty(), b.is_empty()) {
+        (true, _) => b.to_string(),
+        (_, true) => a.to_string(),
+        _ => format!("{} {}", a, b),
+    }
+}
@@ -4,76 +4,213 @@ use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc, Mutex,
 };
+use std::time::Duration;
 
 use anyhow::Result;
 use windows::{
-    Foundation::TypedEventHandler,
+    core::HSTRING,
+    Foundation::{Collections::IIterable, TimeSpan, TypedEventHandler},
     Globalization::Language,
     Media::SpeechRecognition::{
         SpeechContinuousRecognitionCompletedEventArgs,
         SpeechContinuousRecognitionResultGeneratedEventArgs,
-        SpeechRecognizer as WinSpeechRecognizer, SpeechRecognizerState,
+        SpeechRecognitionHypothesisGeneratedEventArgs, SpeechRecognitionListConstraint,
+        SpeechRecognitionResultStatus, SpeechRecognizer as WinSpeechRecognizer,
+        SpeechRecognizerState,
     },
 };
 
+use super::{publish, retry, Check, RecognizerOptions, SharedState};
+
 pub struct SpeechRecognizerImpl {
     recognizer: Option<WinSpeechRecognizer>,
     transcription: Arc<Mutex<String>>,
     is_listening: Arc<AtomicBool>,
     is_ready: Arc<AtomicBool>,
+    is_reconnecting: Arc<AtomicBool>,
+    shared: SharedState,
+    // Cleared by stop() so events from a stopped session are ignored
+    session_active: Arc<AtomicBool>,
+    languages: Vec<String>,
+    endpoint_silence: Option<Duration>,
+    grammar: Vec<String>,
+    final_only: bool,
 }
 
 impl SpeechRecognizerImpl {
-    pub fn new(
-        transcription: Arc<Mutex<String>>,
-        is_listening: Arc<AtomicBool>,
-        is_ready: Arc<AtomicBool>,
-    ) -> Result<Self> {
+    pub fn new(shared: SharedState, options: RecognizerOptions) -> Result<Self> {
         Ok(Self {
             recognizer: None,
-            transcription,
-            is_listening,
-            is_ready,
+            transcription: shared.transcription.clone(),
+            is_listening: shared.is_listening.clone(),
+            is_ready: shared.is_ready.clone(),
+            is_reconnecting: shared.is_reconnecting.clone(),
+            shared,
+            session_active: Arc::new(AtomicBool::new(false)),
+            languages: options.languages,
+            endpoint_silence: options.endpoint_silence,
+            grammar: options.grammar,
+            final_only: options.final_only,
         })
     }
 
+    /// Find the first of `tags` among the installed dictation languages, or
+    /// fail listing the ones that are available
+    fn supported_language(tags: &[String]) -> Result<Language> {
+        let installed = installed_languages()?;
+        for tag in tags {
+            if let Some((_, language)) = installed
+                .iter()
+                .find(|(language_tag, _)| language_tag.eq_ignore_ascii_case(tag))
+            {
+                log::info!("Using speech language {}", tag);
+                return Ok(language.clone());
+            }
+            log::debug!("Speech language {} is not installed", tag);
+        }
+        let tag = tags.join("', '");
+
+        let available: Vec<String> = installed.into_iter().map(|(tag, _)| tag).collect();
+
+        if available.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Speech language '{}' is not installed, and no speech languages are. \
+                 Add one under Settings > Time & Language > Speech.",
+                tag
+            ));
+        }
+        Err(anyhow::anyhow!(
+            "Speech language '{}' is not installed. Available: {}. \
+             More can be added under Settings > Time & Language > Speech.",
+            tag,
+            available.join(", ")
+        ))
+    }
+
     pub fn start(&mut self) -> Result<()> {
-        // Create speech recognizer with system default language
-        let language = Language::CreateLanguage(&windows::core::HSTRING::from("en-US"))
-            .map_err(|e| anyhow::anyhow!("Failed to create language: {}", e))?;
+        // Use the first requested language that's installed, else the system
+        // speech language
+        let language = if self.languages.is_empty() {
+            WinSpeechRecognizer::SystemSpeechLanguage()
+                .map_err(|e| anyhow::anyhow!("Failed to get system speech language: {}", e))?
+        } else {
+            Self::supported_language(&self.languages)?
+        };
+        if let Ok(tag) = language.LanguageTag() {
+            self.shared.set_locale(tag.to_string());
+        }
         let recognizer = WinSpeechRecognizer::Create(&language)
             .map_err(|e| anyhow::anyhow!("Failed to create speech recognizer: {}", e))?;
 
-        // Compile the default dictation grammar
-        let compile_op = recognizer
-            .CompileConstraintsAsync()
-            .map_err(|e| anyhow::anyhow!("Failed to compile constraints: {}", e))?;
+        if let Some(silence) = self.endpoint_silence {
+            // TimeSpan counts 100ns ticks
+            let timeout = TimeSpan {
+                Duration: (silence.as_micros() * 10) as i64,
+            };
+            recognizer
+                .Timeouts()
+                .and_then(|timeouts| timeouts.SetEndSilenceTimeout(timeout))
+                .map_err(|e| anyhow::anyhow!("Failed to set the end silence timeout: {}", e))?;
+        }
 
-        // Block until compilation completes
-        compile_op
-            .get()
-            .map_err(|e| anyhow::anyhow!("Failed to compile grammar: {}", e))?;
+        // --grammar: only listen for the listed phrases instead of dictation
+        if !self.grammar.is_empty() {
+            let phrases: Vec<HSTRING> = self.grammar.iter().map(HSTRING::from).collect();
+            let constraint = IIterable::<HSTRING>::try_from(phrases)
+                .and_then(|phrases| SpeechRecognitionListConstraint::Create(&phrases))
+                .map_err(|e| anyhow::anyhow!("Failed to create the grammar: {}", e))?;
+            recognizer
+                .Constraints()
+                .and_then(|constraints| constraints.Append(&constraint))
+                .map_err(|e| anyhow::anyhow!("Failed to add the grammar: {}", e))?;
+        }
+
+        // Compile the grammar (dictation unless --grammar; can fail
+        // transiently while the speech service starts up, so retry)
+        retry(&self.is_reconnecting, || {
+            let compile_op = recognizer
+                .CompileConstraintsAsync()
+                .map_err(|e| anyhow::anyhow!("Failed to compile constraints: {}", e))?;
+
+            // Block until compilation completes
+            compile_op
+                .get()
+                .map_err(|e| anyhow::anyhow!("Failed to compile grammar: {}", e))
+        })?;
 
         // Get continuous recognition session
         let session = recognizer
             .ContinuousRecognitionSession()
             .map_err(|e| anyhow::anyhow!("Failed to get recognition session: {}", e))?;
 
-        // Set up result handler for intermediate results (hypotheses)
+        self.session_active = Arc::new(AtomicBool::new(true));
+
+        // Phrases completed so far; the live transcription is these plus the
+        // current hypothesis
+        let committed = Arc::new(Mutex::new(String::new()));
+
+        // Set up hypothesis handler for word-by-word partial results
+        let transcription_for_hypothesis = Arc::clone(&self.transcription);
+        let text_changed_for_hypothesis = Arc::clone(&self.shared.text_changed);
+        let committed_for_hypothesis = Arc::clone(&committed);
+        let session_active_for_hypothesis = Arc::clone(&self.session_active);
+
+        let hypothesis_handler = TypedEventHandler::new(
+            move |_sender: &Option<WinSpeechRecognizer>,
+                  args: &Option<SpeechRecognitionHypothesisGeneratedEventArgs>| {
+                if !session_active_for_hypothesis.load(Ordering::SeqCst) {
+                    return Ok(());
+                }
+                let Some(text) = args
+                    .as_ref()
+                    .and_then(|a| a.Hypothesis().ok())
+                    .and_then(|h| h.Text().ok())
+                else {
+                    return Ok(());
+                };
+                let committed = committed_for_hypothesis.lock().unwrap();
+                publish(
+                    &transcription_for_hypothesis,
+                    &text_changed_for_hypothesis,
+                    &join_phrase(&committed, &text.to_string()),
+                );
+                Ok(())
+            },
+        );
+
+        // With --no-partial only completed phrases are shown
+        if !self.final_only {
+            recognizer
+                .HypothesisGenerated(&hypothesis_handler)
+                .map_err(|e| anyhow::anyhow!("Failed to register hypothesis handler: {}", e))?;
+        }
+
+        // Set up result handler for completed phrases (stabilizes the text)
         let transcription_for_result = Arc::clone(&self.transcription);
+        let text_changed_for_result = Arc::clone(&self.shared.text_changed);
         let is_listening_for_result = Arc::clone(&self.is_listening);
+        let committed_len_for_result = Arc::clone(&self.shared.committed_len);
+        let session_active_for_result = Arc::clone(&self.session_active);
 
         let result_handler = TypedEventHandler::new(
             move |_sender: &Option<_>,
                   args: &Option<SpeechContinuousRecognitionResultGeneratedEventArgs>| {
+                if !session_active_for_result.load(Ordering::SeqCst) {
+                    return Ok(());
+                }
                 if let Some(args) = args {
                     if let Ok(result) = args.Result() {
                         if let Ok(text) = result.Text() {
                             let text_str = text.to_string();
                             if !text_str.is_empty() {
-                                if let Ok(mut trans) = transcription_for_result.lock() {
-                                    *trans = text_str;
-                                }
+                                let mut committed = committed.lock().unwrap();
+                                *committed = join_phrase(&committed, &text_str);
+                                publish(
+                                    &transcription_for_result,
+                                    &text_changed_for_result,
+                                    &committed,
+                                );
+                                committed_len_for_result.store(committed.len(), Ordering::SeqCst);
                                 is_listening_for_result.store(true, Ordering::SeqCst);
                             }
                         }
@@ -90,12 +227,29 @@ impl SpeechRecognizerImpl {
         // Set up completion handler
         let is_listening_for_complete = Arc::clone(&self.is_listening);
         let is_ready_for_complete = Arc::clone(&self.is_ready);
+        let shared_for_complete = self.shared.clone();
+        let session_active_for_complete = Arc::clone(&self.session_active);
 
         let completed_handler = TypedEventHandler::new(
             move |_sender: &Option<_>,
-                  _args: &Option<SpeechContinuousRecognitionCompletedEventArgs>| {
+                  args: &Option<SpeechContinuousRecognitionCompletedEventArgs>| {
+                if !session_active_for_complete.load(Ordering::SeqCst) {
+                    return Ok(());
+                }
                 is_listening_for_complete.store(false, Ordering::SeqCst);
                 is_ready_for_complete.store(false, Ordering::SeqCst);
+
+                // Surface failures (network, audio, timeouts); our own stop is UserCanceled
+                if let Some(status) = args.as_ref().and_then(|a| a.Status().ok()) {
+                    log::info!("Recognition completed: {:?}", status);
+                    if status != SpeechRecognitionResultStatus::Success
+                        && status != SpeechRecognitionResultStatus::UserCanceled
+                    {
+                        shared_for_complete
+                            .report_error(format!("Recognition stopped: {:?}", status));
+                    }
+                }
+                shared_for_complete.completion.signal();
                 Ok(())
             },
         );
@@ -113,14 +267,19 @@ impl SpeechRecognizerImpl {
             .get()
             .map_err(|e| anyhow::anyhow!("Failed to start recognition session: {}", e))?;
 
+        // Windows captures the microphone itself and reports a missing one
+        // through the Completed status, so a started session counts as audio
+        self.shared.audio.mark();
         self.is_ready.store(true, Ordering::SeqCst);
         self.is_listening.store(true, Ordering::SeqCst);
         self.recognizer = Some(recognizer);
+        log::info!("Recognizer ready");
 
         Ok(())
     }
 
     pub fn stop(&mut self) {
+        log::debug!("Stopping recognition");
         self.is_listening.store(false, Ordering::SeqCst);
 
         if let Some(ref recognizer) = self.recognizer {
@@ -139,12 +298,64 @@ impl SpeechRecognizerImpl {
             }
         }
 
+        // StopAsync has completed, so any final result was delivered; ignore
+        // whatever the old session reports from here on
+        self.session_active.store(false, Ordering::SeqCst);
         self.recognizer = None;
     }
 }
 
+/// Tags of the installed speech languages, for `claudio locales`
+pub fn supported_languages() -> Result<Vec<String>> {
+    Ok(installed_languages()?
+        .into_iter()
+        .map(|(tag, _)| tag)
+        .collect())
+}
+
+/// `claudio doctor` checks: the installed speech languages
+pub fn diagnose() -> Vec<Check> {
+    let languages = supported_languages().and_then(|languages| {
+        if languages.is_empty() {
+            Err(anyhow::anyhow!(
+                "None installed (add one in Settings > Time & language > Speech)"
+            ))
+        } else {
+            Ok(languages.join(", "))
+        }
+    });
+    vec![Check::new("languages", languages)]
+}
+
+/// Installed speech languages with their tags
+fn installed_languages() -> Result<Vec<(String, Language)>> {
+    let supported = WinSpeechRecognizer::SupportedTopicLanguages()
+        .map_err(|e| anyhow::anyhow!("Failed to list speech languages: {}", e))?;
+
+    let mut languages = Vec::new();
+    for i in 0..supported.Size().unwrap_or(0) {
+        let Ok(language) = supported.GetAt(i) else {
+            continue;
+        };
+        let Ok(tag) = language.LanguageTag() else {
+            continue;
+        };
+        languages.push((tag.to_string(), language));
+    }
+    Ok(languages)
+}
+
 impl Drop for SpeechRecognizerImpl {
     fn drop(&mut self) {
         self.stop();
     }
 }
+
+/// Append a phrase to the text recognized so far, separated by a space
+fn join_phrase(committed: &str, phrase: &str) -> String {
+    if committed.is_empty() {
+        phrase.to_string()
+    } else {
+        format!("{} {}", committed, phrase)
+    }
+}
@@ -0,0 +1,123 @@
+//! Latency measurements for `--stats`, printed to stderr on exit
+//!
+//! - startup: until the first audio buffer and until the recognizer reported
+//!   ready (on macOS the difference is the `--warmup-ms` warmup)
+//! - update delay: from a recognizer changing the transcription until it was
+//!   on screen (or handed to `--stream-exec` when headless)
+//! - frames: how often the terminal was redrawn
+//! - finalize: from finishing (Enter, a stop condition, Ctrl+C headless)
+//!   until the final text was ready for output
+
+use std::time::{Duration, Instant};
+
+use claudio::SharedState;
+
+pub struct Stats {
+    start: Instant,
+    first_audio: Option<Duration>,
+    ready: Option<Duration>,
+    last_update: Option<Instant>, // Latest transcription change already counted
+    delays: Vec<Duration>,
+    frames: u64,
+    finish_requested: Option<Instant>,
+    finalize: Option<Duration>,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            first_audio: None,
+            ready: None,
+            last_update: None,
+            delays: Vec::new(),
+            frames: 0,
+            finish_requested: None,
+            finalize: None,
+        }
+    }
+
+    /// Note startup progress (only the first session is measured)
+    pub fn check_startup(&mut self, shared: &SharedState, is_ready: bool) {
+        if self.first_audio.is_none() {
+            self.first_audio = shared
+                .audio
+                .first()
+                .map(|first| first.saturating_duration_since(self.start));
+        }
+        if self.ready.is_none() && is_ready {
+            self.ready = Some(self.start.elapsed());
+        }
+    }
+
+    /// The transcription as of `changed` (its change time, read before the
+    /// text) is now shown
+    pub fn shown(&mut self, changed: Option<Instant>) {
+        if let Some(changed) = changed {
+            if self.last_update != Some(changed) {
+                self.delays.push(changed.elapsed());
+                self.last_update = Some(changed);
+            }
+        }
+    }
+
+    /// A frame was drawn
+    pub fn frame(&mut self) {
+        self.frames += 1;
+    }
+
+    /// Recording was told to finish; the first request counts
+    pub fn finish_requested(&mut self) {
+        self.finish_requested.get_or_insert_with(Instant::now);
+    }
+
+    /// The final text is ready for output
+    pub fn finalized(&mut self) {
+        if let Some(requested) = self.finish_requested {
+            self.finalize = Some(requested.elapsed());
+        }
+    }
+
+    /// Write the report to stderr
+    pub fn print(&self) {
+        eprintln!("claudio stats:");
+        eprintln!(
+            "  startup:      first audio {}, ready {}",
+            format_ms(self.first_audio),
+            format_ms(self.ready)
+        );
+
+        let mut delays = self.delays.clone();
+        delays.sort();
+        if delays.is_empty() {
+            eprintln!("  update delay: no updates");
+        } else {
+            let total: Duration = delays.iter().sum();
+            let percentile = |p: usize| delays[(delays.len() - 1) * p / 100];
+            eprintln!(
+                "  update delay: {} updates, mean {}, p50 {}, p95 {}, max {}",
+                delays.len(),
+                format_ms(Some(total / delays.len() as u32)),
+                format_ms(Some(percentile(50))),
+                format_ms(Some(percentile(95))),
+                format_ms(delays.last().copied())
+            );
+        }
+
+        let secs = self.start.elapsed().as_secs_f64();
+        eprintln!(
+            "  frames:       {} in {:.1}s ({:.1}/s)",
+            self.frames,
+            secs,
+            self.frames as f64 / secs
+        );
+        eprintln!("  finalize:     {}", format_ms(self.finalize));
+    }
+}
+
+fn format_ms(duration: Option<Duration>) -> String {
+    match duration {
+        Some(duration) => format!("{:.1}ms", duration.as_secs_f64() * 1000.0),
+        None => "-".to_string(),
+    }
+}
@@ -5,27 +5,197 @@
 //! - Transcribed text with character-by-character fade animation
 //! - Editable text mode for corrections
 //! - Status bar with keyboard shortcuts
+//!
+//! This is the only renderer: `main.rs` owns the state and drives `Ui` through
+//! `InlineTerminal`, so spinner, fade and wrapping behavior live here alone.
+
+use std::borrow::Cow;
+use std::env;
+use std::str::FromStr;
 
-use termwiz::cell::{Cell, CellAttributes};
+use termwiz::cell::{grapheme_column_width, Cell, CellAttributes};
 use termwiz::color::ColorAttribute;
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::inline_term::InlineSurface;
+use crate::keymap::{Action, Keymap};
+use crate::messages::{Label, Messages};
 
 // Animation constants
-const LOADING_FRAMES: [&str; 12] = ["⠋", "⠙", "⠹", "⠸", "⢰", "⣰", "⣠", "⣄", "⣆", "⡆", "⠇", "⠏"];
-const RECORDING_FRAMES: [&str; 3] = ["●", "◎", "◉"];
 const CHAR_FADE_DELAY_MS: f32 = 20.0;
 const CHAR_FADE_DURATION_MS: f32 = 1500.0;
+/// Share of the fading text that must survive a recognizer update for its
+/// fade to carry on; below this the update counts as a new hypothesis
+const MIN_KEPT_FRACTION: f32 = 0.5;
+/// Largest old × new unstable text aligned; longer text just restarts its fade
+const MAX_ALIGN_CELLS: usize = 250_000;
+/// How long text the recognizer rewrote stays highlighted
+const CORRECTION_FLASH_MS: f32 = 300.0;
+/// Seconds left shown in place of the spinner before an auto-stop
+const COUNTDOWN_DIGITS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+
+/// Longest a new character waits for the ones before it to start fading in.
+/// Bursts (a Whisper window, a long final result) would otherwise queue up
+/// behind each other and fall further behind the speech as a session goes on.
+const MAX_APPEAR_LAG_MS: f32 = 500.0;
+
+// Smallest terminal with room for the spinner, some text and the controls
+// bar; in anything smaller only the spinner is shown
+const MIN_WIDTH: usize = 6;
+const MIN_HEIGHT: usize = 2;
+
+/// Timing of the per-character fade-in (`--char-delay-ms`, `--fade-ms`)
+#[derive(Clone, Copy, Debug)]
+pub struct Animation {
+    /// Delay between successive characters starting to fade in
+    pub char_delay_ms: f32,
+    /// Time for a character to settle from cyan to white (0 disables the fade)
+    pub fade_ms: f32,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Self {
+            char_delay_ms: CHAR_FADE_DELAY_MS,
+            fade_ms: CHAR_FADE_DURATION_MS,
+        }
+    }
+}
+
+/// Symbols used for the spinner and controls bar
+pub struct Glyphs {
+    loading: &'static [&'static str],
+    listening: &'static [&'static str],
+    idle: &'static str,
+    separator: &'static str,
+    more: &'static str,
+}
+
+impl Glyphs {
+    pub const UNICODE: Glyphs = Glyphs {
+        loading: &["⠋", "⠙", "⠹", "⠸", "⢰", "⣰", "⣠", "⣄", "⣆", "⡆", "⠇", "⠏"],
+        listening: &["●", "◎", "◉"],
+        idle: "○",
+        separator: " • ",
+        more: "↑",
+    };
+
+    /// For terminals and fonts without those symbols (`--ascii`)
+    pub const ASCII: Glyphs = Glyphs {
+        loading: &["|", "/", "-", "\\"],
+        listening: &["*"],
+        idle: "o",
+        separator: " | ",
+        more: "^",
+    };
+
+    /// Detect from the environment: ASCII when the locale isn't UTF-8, or in
+    /// a legacy Windows console (anything but Windows Terminal)
+    pub fn detect() -> &'static Glyphs {
+        if cfg!(target_os = "windows") {
+            return if env::var_os("WT_SESSION").is_some() {
+                &Self::UNICODE
+            } else {
+                &Self::ASCII
+            };
+        }
+
+        // The first locale variable set decides, as in setlocale()
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|var| env::var(var).ok().filter(|v| !v.is_empty()));
+        match locale {
+            Some(locale) => {
+                let locale = locale.to_ascii_lowercase();
+                if locale.contains("utf-8") || locale.contains("utf8") {
+                    &Self::UNICODE
+                } else {
+                    &Self::ASCII
+                }
+            }
+            // Nothing configured: assume a modern terminal
+            None => &Self::UNICODE,
+        }
+    }
+}
 
 /// Spinner display state
 #[derive(Clone, Copy, PartialEq, Eq, Default)]
 pub enum SpinnerState {
     #[default]
     Loading,
+    LoadingModel,
+    Reconnecting,
     Listening,
     Idle,
 }
 
+/// How colors are emitted
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    /// No colors at all (`NO_COLOR`)
+    None,
+    /// 256-color palette
+    Palette,
+    /// 24-bit RGB
+    #[default]
+    TrueColor,
+}
+
+impl ColorMode {
+    /// Detect from the environment: `NO_COLOR` disables colors, `COLORTERM`
+    /// (or Windows Terminal) enables truecolor, otherwise use the palette.
+    pub fn detect() -> Self {
+        if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return ColorMode::None;
+        }
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" || env::var_os("WT_SESSION").is_some() {
+            ColorMode::TrueColor
+        } else {
+            ColorMode::Palette
+        }
+    }
+}
+
+/// Terminal background, which decides the color text settles to
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Background {
+    /// Light text on a dark background
+    #[default]
+    Dark,
+    /// Dark text on a light background
+    Light,
+}
+
+impl Background {
+    /// Detect from `COLORFGBG` ("fg;bg" palette indexes, set by rxvt,
+    /// Konsole, iTerm2 and others). Assumes dark when it isn't set.
+    pub fn detect() -> Self {
+        let colorfgbg = env::var("COLORFGBG").unwrap_or_default();
+        match colorfgbg
+            .rsplit(';')
+            .next()
+            .and_then(|bg| bg.parse::<u8>().ok())
+        {
+            Some(7 | 9..=15) => Background::Light,
+            _ => Background::Dark,
+        }
+    }
+}
+
+impl FromStr for Background {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "dark" => Ok(Background::Dark),
+            "light" => Ok(Background::Light),
+            _ => Err(()),
+        }
+    }
+}
+
 /// UI interaction mode
 #[derive(Clone, Copy, PartialEq, Eq, Default)]
 pub enum Mode {
@@ -34,90 +204,129 @@ pub enum Mode {
     Editing,
 }
 
+/// Picks a control's label from the UI strings
+type LabelFn = fn(&Messages) -> &Label;
+
 /// A keyboard shortcut for the controls bar
 struct Control {
-    key: &'static str,
-    label: &'static str,
-    short: &'static str,
+    key: Cow<'static, str>,
+    label: LabelFn,
     color: u8, // Palette index
 }
 
-const CONTROLS_LISTENING: &[Control] = &[
-    Control {
-        key: "Enter",
-        label: "submit",
-        short: "sub",
-        color: 3,
-    },
-    Control {
-        key: "^E",
-        label: "edit",
-        short: "edt",
-        color: 5,
-    },
-    Control {
-        key: "^D",
-        label: "discard",
-        short: "dis",
-        color: 4,
-    },
-    Control {
-        key: "^C",
-        label: "cancel",
-        short: "esc",
-        color: 1,
-    },
+/// Recording mode controls in display order: (action, label, color).
+/// Key names come from the active keymap.
+const CONTROLS_LISTENING: &[(Action, LabelFn, u8)] = &[
+    (Action::Finish, |m| &m.submit, 3),
+    (Action::Edit, |m| &m.edit, 5),
+    (Action::Restart, |m| &m.discard, 4),
+    (Action::ClearLive, |m| &m.clear, 2),
+    (Action::Pause, |m| &m.pause, 6),
+    (Action::Cancel, |m| &m.cancel, 1),
 ];
 
 const CONTROLS_EDITING: &[Control] = &[
     Control {
-        key: "^S",
-        label: "save",
-        short: "sav",
+        key: Cow::Borrowed("Enter"),
+        label: |m| &m.submit,
+        color: 3,
+    },
+    Control {
+        key: Cow::Borrowed("^S"),
+        label: |m| &m.save,
         color: 3,
     },
     Control {
-        key: "^E",
-        label: "editor",
-        short: "edt",
+        key: Cow::Borrowed("^E"),
+        label: |m| &m.editor,
         color: 5,
     },
     Control {
-        key: "^D",
-        label: "discard",
-        short: "dis",
+        key: Cow::Borrowed("^D"),
+        label: |m| &m.revert,
         color: 1,
     },
     Control {
-        key: "←→",
-        label: "move",
-        short: "mv",
+        key: Cow::Borrowed("←→"),
+        label: |m| &m.move_cursor,
         color: 8,
     },
 ];
 
+/// What a drawn frame showed, so an unchanged screen isn't drawn again
+#[derive(Clone, PartialEq)]
+struct FrameState {
+    spinner_state: SpinnerState,
+    spinner: &'static str,
+    text: String,
+    stable_len: usize,
+    appear_ms: Vec<f32>,
+    correction: Option<Correction>,
+    animating: bool, // One more frame is drawn after the fade settles
+    mode: Mode,
+    cursor_pos: usize,
+    show_placeholder: bool,
+    hint: Option<String>,
+    show_controls: bool,
+    engine_status: Vec<String>,
+    error: Option<String>,
+    warning: Option<String>,
+}
+
 /// Main UI state and renderer
 pub struct Ui {
     // Spinner state
     pub spinner_state: SpinnerState,
     spinner_frame: usize,
+    pub countdown: Option<u64>, // Seconds left before an auto-stop, shown instead
 
     // Text state:
     // - frozen_text: from confirmed edits, always white
     // - text: current speech transcription
+    // - frozen_tail: confirmed text after the edit cursor; new speech is
+    //   inserted before it
     // - stable_len: chars that are stable (white, no animation)
+    // - appear_ms: when each char after stable_len starts fading in
+    // - correction: the text the recognizer last rewrote, briefly highlighted
     frozen_text: String,
     text: String,
+    frozen_tail: String,
     stable_len: usize,
-    animation_start_ms: f32,
+    appear_ms: Vec<f32>,
+    correction: Option<Correction>,
+    pub animation: Animation,
 
     // Editing state
     pub mode: Mode,
-    cursor_pos: usize, // Character index (not byte)
+    cursor_pos: usize,     // Character index (not byte), on a grapheme boundary
+    edit_original: String, // Full text when editing started (restored on cancel)
 
     // Visibility flags
     pub show_placeholder: bool,
+    pub hint: Option<String>, // Replaces the placeholder (e.g. while --push-to-talk's key is up)
     pub show_controls: bool,
+    // Engine, language and where it runs (--show-status), right-aligned on
+    // the controls bar; empty when hidden
+    pub engine_status: Vec<String>,
+    pub color_mode: ColorMode,
+    pub background: Background,
+    pub glyphs: &'static Glyphs,
+    pub messages: Messages,
+    pub word_wrap: bool, // Wrap at spaces rather than mid-word
+
+    // Recognizer error, shown in place of the controls bar
+    pub error: Option<String>,
+    // Hint about a likely problem (e.g. no audio), shown in place of the controls bar
+    pub warning: Option<String>,
+
+    // Controls bar for recording mode (generated from the keymap)
+    listening_controls: Vec<Control>,
+
+    // Terminal height the display has to fit in
+    screen_rows: usize,
+
+    // State as of the last render
+    last_frame: Option<FrameState>,
 }
 
 impl Ui {
@@ -125,18 +334,65 @@ impl Ui {
         Self {
             spinner_state: SpinnerState::Loading,
             spinner_frame: 0,
+            countdown: None,
             frozen_text: String::new(),
             text: String::new(),
+            frozen_tail: String::new(),
             stable_len: 0,
-            animation_start_ms: 0.0,
+            appear_ms: Vec::new(),
+            correction: None,
+            animation: Animation::default(),
             mode: Mode::Listening,
             cursor_pos: 0,
+            edit_original: String::new(),
             show_placeholder: false,
+            hint: None,
             show_controls: false,
+            engine_status: Vec::new(),
+            color_mode: ColorMode::default(),
+            background: Background::default(),
+            glyphs: &Glyphs::UNICODE,
+            messages: Messages::default(),
+            word_wrap: true,
+            error: None,
+            warning: None,
+            listening_controls: Self::listening_controls(&Keymap::default()),
+            screen_rows: usize::MAX,
+            last_frame: None,
         }
     }
 
-    /// Advance spinner animation frame
+    /// Show the bindings from `keymap` in the recording mode controls bar
+    pub fn set_keymap(&mut self, keymap: &Keymap) {
+        self.listening_controls = Self::listening_controls(keymap);
+    }
+
+    fn listening_controls(keymap: &Keymap) -> Vec<Control> {
+        CONTROLS_LISTENING
+            .iter()
+            .filter_map(|&(action, label, color)| {
+                let chord = keymap.chord(action)?;
+                Some(Control {
+                    key: Cow::Owned(chord.to_string()),
+                    label,
+                    color,
+                })
+            })
+            .collect()
+    }
+
+    /// Fit the display into a terminal `rows` high
+    pub fn set_screen_rows(&mut self, rows: usize) {
+        self.screen_rows = rows;
+    }
+
+    /// Whether the terminal is too small for anything but the spinner
+    fn is_compact(&self, width: usize) -> bool {
+        width < MIN_WIDTH || self.screen_rows < MIN_HEIGHT
+    }
+
+    /// Advance spinner animation frame (the glyph changes every frame while
+    /// loading, every fourth while listening)
     pub fn tick(&mut self) {
         self.spinner_frame = self.spinner_frame.wrapping_add(1);
     }
@@ -154,140 +410,164 @@ impl Ui {
             return;
         }
 
-        // Find first differing character between current text and new text
-        let common_prefix_len = self
-            .text
-            .chars()
-            .zip(text.chars())
-            .take_while(|(a, b)| a == b)
-            .count();
-
-        let new_text_len = text.chars().count();
-
-        // Stable portion = common prefix (text that didn't change)
-        // But never decrease stable_len - once stable, stays stable
-        let new_stable_len = common_prefix_len.max(self.stable_len.min(new_text_len));
-
-        // Handle animation timing for unstable text
-        if new_text_len > new_stable_len {
-            if self.text.is_empty() || new_stable_len != self.stable_len {
-                // First text or stable boundary changed - start animation now
-                self.animation_start_ms = elapsed_ms;
-            } else {
-                // Compare unstable portions to detect content changes vs extensions
-                let old_unstable: String = self.text.chars().skip(self.stable_len).collect();
-                let new_unstable: String = text.chars().skip(new_stable_len).collect();
-
-                if new_unstable.starts_with(&old_unstable) {
-                    // New text extends old unstable text - adjust timing for new chars
-                    let new_chars = new_unstable.chars().count() - old_unstable.chars().count();
-                    if new_chars > 0 {
-                        self.animation_start_ms -= new_chars as f32 * CHAR_FADE_DELAY_MS;
-                    }
-                } else {
-                    // Unstable portion content changed (correction) - reset animation
-                    self.animation_start_ms = elapsed_ms;
-                }
-            }
+        let (stable_len, appear_ms) = stable_update(
+            &self.text,
+            self.stable_len,
+            &self.appear_ms,
+            text,
+            elapsed_ms,
+            &self.animation,
+        );
+        if !self.text.is_empty() && !text.starts_with(self.text.as_str()) {
+            self.correction = Some(Correction::new(&self.text, text, elapsed_ms));
         }
-
-        self.stable_len = new_stable_len;
+        self.stable_len = stable_len;
+        self.appear_ms = appear_ms;
         self.text = text.to_string();
     }
 
-    /// Get the full transcription text (frozen + speech text)
+    /// Get the full transcription text (frozen + speech text + frozen tail)
     pub fn full_text(&self) -> String {
-        format!("{}{}", self.frozen_text, self.text)
+        format!(
+            "{}{}{}{}",
+            self.frozen_text,
+            self.text,
+            self.tail_separator(),
+            self.frozen_tail
+        )
+    }
+
+    /// Characters of the full text outside the live speech text
+    pub fn confirmed_chars(&self) -> usize {
+        self.full_text().chars().count() - self.text.chars().count()
+    }
+
+    /// Space between speech inserted mid-text and the text after it
+    fn tail_separator(&self) -> &'static str {
+        let separated = self.text.is_empty()
+            || self.text.ends_with(char::is_whitespace)
+            || self.frozen_tail.is_empty()
+            || self.frozen_tail.starts_with(char::is_whitespace);
+        if separated {
+            ""
+        } else {
+            " "
+        }
     }
 
     /// Check if there's any text content
     pub fn is_empty(&self) -> bool {
-        self.frozen_text.is_empty() && self.text.is_empty()
+        self.frozen_text.is_empty() && self.text.is_empty() && self.frozen_tail.is_empty()
     }
 
-    /// Clear transcription and reset animation
-    #[allow(dead_code)]
-    pub fn clear(&mut self) {
+    /// Full reset (for restart)
+    pub fn reset(&mut self) {
         self.frozen_text.clear();
         self.text.clear();
+        self.frozen_tail.clear();
         self.stable_len = 0;
-        self.animation_start_ms = 0.0;
+        self.appear_ms.clear();
+        self.correction = None;
         self.cursor_pos = 0;
+        self.mode = Mode::Listening;
     }
 
-    /// Full reset (for restart)
-    pub fn reset(&mut self) {
-        self.frozen_text.clear();
+    /// Drop the live transcription, keeping the confirmed text around it
+    pub fn clear_live(&mut self) {
         self.text.clear();
         self.stable_len = 0;
-        self.animation_start_ms = 0.0;
-        self.cursor_pos = 0;
-        self.mode = Mode::Listening;
+        self.appear_ms.clear();
+        self.correction = None;
     }
 
     /// Set frozen text directly (for external editor results)
     pub fn set_frozen_text(&mut self, text: String) {
         self.frozen_text = text;
         self.text.clear();
+        self.frozen_tail.clear();
         self.stable_len = 0;
-        self.animation_start_ms = 0.0;
+        self.appear_ms.clear();
+        self.correction = None;
         self.cursor_pos = 0;
         self.mode = Mode::Listening;
     }
 
+    /// Fold the live speech text into the frozen text (e.g. when pausing),
+    /// so a new recognition session can start from an empty transcription
+    pub fn freeze_text(&mut self) {
+        let separator = self.tail_separator();
+        self.frozen_text.push_str(&self.text);
+        self.frozen_text.push_str(separator);
+        self.text.clear();
+        self.stable_len = 0;
+        self.appear_ms.clear();
+        self.correction = None;
+        self.ensure_trailing_space();
+    }
+
     // --- Editing mode ---
 
     /// Enter editing mode - combines all text into frozen for editing
     pub fn start_editing(&mut self) {
         self.mode = Mode::Editing;
+        // Cursor where speech was going in (the end, unless inserting mid-text)
+        self.cursor_pos = self.full_text().chars().count() - self.frozen_tail.chars().count();
         // Combine all text into frozen for editing
         let full = self.full_text();
+        self.edit_original.clone_from(&full);
         self.frozen_text = full;
         self.text.clear();
+        self.frozen_tail.clear();
         self.stable_len = 0;
-        self.cursor_pos = self.frozen_text.chars().count(); // Cursor at end
+        self.appear_ms.clear();
+        self.correction = None;
     }
 
-    /// Exit editing mode, keeping changes
-    #[allow(dead_code)]
-    pub fn finish_editing(&mut self) {
-        self.mode = Mode::Listening;
-    }
-
-    /// Exit editing mode and freeze the current text (no animation)
+    /// Exit editing mode and freeze the current text (no animation). New
+    /// speech is inserted at the cursor: text after it becomes the frozen
+    /// tail, and a space is added in front so words don't run together.
     pub fn finish_editing_with_freeze(&mut self) {
         // frozen_text already contains the edited text from start_editing
+        let split = self.char_to_byte_index(self.cursor_pos);
+        // Whitespace at the split stays in front, so the tail starts at a word
+        let tail = self.frozen_text.split_off(split);
+        let trimmed = tail.trim_start();
+        self.frozen_text
+            .push_str(&tail[..tail.len() - trimmed.len()]);
+        self.frozen_tail = trimmed.to_string();
         self.mode = Mode::Listening;
+        self.ensure_trailing_space();
     }
 
-    /// Ensure frozen text ends with a space (for separation from new speech)
+    /// Ensure frozen text ends with whitespace (for separation from new
+    /// speech); a line break from a voice command already separates it
     pub fn ensure_trailing_space(&mut self) {
-        if !self.frozen_text.is_empty() && !self.frozen_text.ends_with(' ') {
+        if !self.frozen_text.is_empty() && !self.frozen_text.ends_with(char::is_whitespace) {
             self.frozen_text.push(' ');
         }
     }
 
-    /// Exit editing mode, discarding changes
-    pub fn cancel_editing(&mut self, original: &str) {
-        self.frozen_text = original.to_string();
+    /// Exit editing mode, restoring the text as it was when editing started
+    pub fn cancel_editing(&mut self) {
+        self.frozen_text = std::mem::take(&mut self.edit_original);
         self.text.clear();
+        self.frozen_tail.clear();
         self.stable_len = 0;
+        self.appear_ms.clear();
+        self.correction = None;
         self.mode = Mode::Listening;
+        self.ensure_trailing_space();
     }
 
-    /// Move cursor left
+    /// Move cursor left, over a whole grapheme (e.g. a flag emoji or a
+    /// letter with combining accents)
     pub fn cursor_left(&mut self) {
-        if self.cursor_pos > 0 {
-            self.cursor_pos -= 1;
-        }
+        self.cursor_pos = self.prev_boundary();
     }
 
-    /// Move cursor right
+    /// Move cursor right, over a whole grapheme
     pub fn cursor_right(&mut self) {
-        let len = self.frozen_text.chars().count();
-        if self.cursor_pos < len {
-            self.cursor_pos += 1;
-        }
+        self.cursor_pos = self.next_boundary();
     }
 
     /// Move cursor to start
@@ -307,24 +587,55 @@ impl Ui {
         self.cursor_pos += 1;
     }
 
-    /// Delete character before cursor (backspace)
+    /// Delete the grapheme before the cursor (backspace)
     pub fn delete_back(&mut self) {
-        if self.cursor_pos > 0 {
-            self.cursor_pos -= 1;
-            let byte_pos = self.char_to_byte_index(self.cursor_pos);
-            let next_byte = self.char_to_byte_index(self.cursor_pos + 1);
-            self.frozen_text.drain(byte_pos..next_byte);
-        }
+        let start = self.prev_boundary();
+        let byte_pos = self.char_to_byte_index(start);
+        let next_byte = self.char_to_byte_index(self.cursor_pos);
+        self.frozen_text.drain(byte_pos..next_byte);
+        self.cursor_pos = start;
     }
 
-    /// Delete character at cursor (delete key)
+    /// Delete the grapheme at the cursor (delete key)
     pub fn delete_forward(&mut self) {
-        let len = self.frozen_text.chars().count();
-        if self.cursor_pos < len {
-            let byte_pos = self.char_to_byte_index(self.cursor_pos);
-            let next_byte = self.char_to_byte_index(self.cursor_pos + 1);
-            self.frozen_text.drain(byte_pos..next_byte);
-        }
+        let byte_pos = self.char_to_byte_index(self.cursor_pos);
+        let next_byte = self.char_to_byte_index(self.next_boundary());
+        self.frozen_text.drain(byte_pos..next_byte);
+    }
+
+    /// Delete from the start of the line to the cursor (Ctrl+U)
+    pub fn delete_to_line_start(&mut self) {
+        let end = self.char_to_byte_index(self.cursor_pos);
+        let start = self.frozen_text[..end].rfind('\n').map_or(0, |i| i + 1);
+        self.cursor_pos -= self.frozen_text[start..end].chars().count();
+        self.frozen_text.drain(start..end);
+    }
+
+    /// Delete from the cursor to the end of the line (Ctrl+K)
+    pub fn delete_to_line_end(&mut self) {
+        let start = self.char_to_byte_index(self.cursor_pos);
+        let end = self.frozen_text[start..]
+            .find('\n')
+            .map_or(self.frozen_text.len(), |i| start + i);
+        self.frozen_text.drain(start..end);
+    }
+
+    /// Char index of the grapheme boundary before the cursor
+    fn prev_boundary(&self) -> usize {
+        let byte_pos = self.char_to_byte_index(self.cursor_pos);
+        self.frozen_text[..byte_pos]
+            .graphemes(true)
+            .next_back()
+            .map_or(self.cursor_pos, |g| self.cursor_pos - g.chars().count())
+    }
+
+    /// Char index of the grapheme boundary after the cursor
+    fn next_boundary(&self) -> usize {
+        let byte_pos = self.char_to_byte_index(self.cursor_pos);
+        self.frozen_text[byte_pos..]
+            .graphemes(true)
+            .next()
+            .map_or(self.cursor_pos, |g| self.cursor_pos + g.chars().count())
     }
 
     fn char_to_byte_index(&self, char_idx: usize) -> usize {
@@ -339,40 +650,72 @@ impl Ui {
 
     /// Calculate lines needed to display current content
     pub fn lines_needed(&self, width: usize) -> usize {
-        if width == 0 {
+        if self.is_compact(width) {
             return 1;
         }
 
-        // First line has spinner (2 chars), rest are full width
-        let first_line_width = width.saturating_sub(2);
-        let char_count = self.total_char_count();
+        // First line has spinner (2 columns), rest are full width; each
+        // newline starts a fresh row at column 0
+        let mut content_lines = wrapped_rows(&self.full_text(), 2, width, self.word_wrap);
 
-        let content_lines =
-            if char_count == 0 || first_line_width == 0 || char_count <= first_line_width {
-                1
-            } else {
-                // First line fills, then full-width lines
-                let remaining = char_count - first_line_width;
-                1 + remaining.div_ceil(width)
-            };
+        // A cursor after a full last row sits at the start of the next one
+        if self.mode == Mode::Editing {
+            content_lines = content_lines.max(self.cursor_layout_position(width).1 + 1);
+        }
 
         // Add controls line if visible
-        if self.show_controls {
+        let lines = if self.show_controls {
             content_lines + 1
         } else {
             content_lines
-        }
+        };
+        lines.min(self.screen_rows)
+    }
+
+    // --- Rendering ---
+
+    /// Whether new text is still fading in at `elapsed_ms`
+    pub fn is_animating(&self, elapsed_ms: f32) -> bool {
+        self.mode == Mode::Listening
+            && (self
+                .appear_ms
+                .iter()
+                .any(|&appear| elapsed_ms < appear + self.animation.fade_ms)
+                || self
+                    .correction
+                    .is_some_and(|c| elapsed_ms < c.time_ms + CORRECTION_FLASH_MS))
     }
 
-    /// Total character count (frozen + speech text)
-    fn total_char_count(&self) -> usize {
-        self.frozen_text.chars().count() + self.text.chars().count()
+    /// Whether a frame rendered at `elapsed_ms` would differ from the last
+    /// one, i.e. the spinner moved, text is fading in, or any state changed
+    pub fn needs_render(&self, elapsed_ms: f32) -> bool {
+        let state = self.frame_state(elapsed_ms);
+        state.animating || self.last_frame.as_ref() != Some(&state)
     }
 
-    // --- Rendering ---
+    fn frame_state(&self, elapsed_ms: f32) -> FrameState {
+        FrameState {
+            spinner_state: self.spinner_state,
+            spinner: self.spinner_glyph().0,
+            text: self.full_text(),
+            stable_len: self.stable_len,
+            appear_ms: self.appear_ms.clone(),
+            correction: self.correction,
+            animating: self.is_animating(elapsed_ms),
+            mode: self.mode,
+            cursor_pos: self.cursor_pos,
+            show_placeholder: self.show_placeholder,
+            hint: self.hint.clone(),
+            show_controls: self.show_controls,
+            engine_status: self.engine_status.clone(),
+            error: self.error.clone(),
+            warning: self.warning.clone(),
+        }
+    }
 
     /// Render the UI to the surface
-    pub fn render(&self, surface: &mut InlineSurface, elapsed_ms: f32) {
+    pub fn render(&mut self, surface: &mut InlineSurface, elapsed_ms: f32) {
+        self.last_frame = Some(self.frame_state(elapsed_ms));
         surface.clear();
         let (width, height) = surface.dimensions();
         if width == 0 || height == 0 {
@@ -389,6 +732,9 @@ impl Ui {
             row,
             Cell::new_grapheme(spinner_char, self.attrs(spinner_color), None),
         );
+        if self.is_compact(width) {
+            return;
+        }
         col += 1;
         surface.set_cell(col, row, Cell::new(' ', CellAttributes::default()));
         col += 1;
@@ -402,10 +748,35 @@ impl Ui {
 
         // Render content based on mode
         if self.is_empty() {
-            if self.show_placeholder {
+            let status = match self.spinner_state {
+                SpinnerState::LoadingModel => Some(&self.messages.loading_model),
+                SpinnerState::Reconnecting => Some(&self.messages.reconnecting),
+                _ => None,
+            };
+            if let Some(status) = status {
+                self.render_text(
+                    surface,
+                    status,
+                    self.attrs(self.dim_color()),
+                    &mut row,
+                    &mut col,
+                    width,
+                    content_rows,
+                );
+            } else if let Some(hint) = &self.hint {
                 self.render_text(
                     surface,
-                    "Speak now...",
+                    hint,
+                    self.attrs(self.dim_color()),
+                    &mut row,
+                    &mut col,
+                    width,
+                    content_rows,
+                );
+            } else if self.show_placeholder {
+                self.render_text(
+                    surface,
+                    &self.messages.speak_now,
                     self.attrs(self.dim_color()),
                     &mut row,
                     &mut col,
@@ -413,35 +784,135 @@ impl Ui {
                     content_rows,
                 );
             }
-        } else if self.mode == Mode::Editing {
-            self.render_editable(surface, &mut row, &mut col, width, content_rows);
         } else {
-            self.render_transcription(surface, elapsed_ms, &mut row, &mut col, width, content_rows);
+            match self.first_visible_row(width, content_rows) {
+                0 => self.render_content(
+                    surface,
+                    elapsed_ms,
+                    &mut row,
+                    &mut col,
+                    width,
+                    content_rows,
+                ),
+                first => {
+                    // Lay out all of the text on a scratch surface and show
+                    // the window starting at `first` below a "more" indicator
+                    let total_rows = wrapped_rows(&self.full_text(), 2, width, self.word_wrap);
+                    let mut full = InlineSurface::new(width, total_rows);
+                    self.render_content(&mut full, elapsed_ms, &mut 0, &mut 2, width, total_rows);
+                    surface.copy_rows(&full, first, 1, content_rows - 1);
+
+                    let more = format!("{} {}", self.glyphs.more, self.messages.more(first));
+                    self.render_text(
+                        surface,
+                        &more,
+                        self.attrs(self.dim_color()),
+                        &mut row,
+                        &mut col,
+                        width,
+                        1,
+                    );
+                }
+            }
         }
 
-        // Render controls on last row
+        // Render controls (or the recognizer error / warning) on last row
         if self.show_controls && height > 0 {
-            self.render_controls(surface, height - 1, width);
+            let red = ColorAttribute::PaletteIndex(1);
+            let yellow = ColorAttribute::PaletteIndex(3);
+            match (&self.error, &self.warning) {
+                (Some(error), _) => self.render_status(surface, error, red, height - 1, width),
+                (None, Some(warning)) => {
+                    self.render_status(surface, warning, yellow, height - 1, width)
+                }
+                (None, None) => {
+                    let used = self.render_controls(surface, height - 1, width);
+                    self.render_engine_status(surface, height - 1, width, used);
+                }
+            }
+        }
+    }
+
+    /// Render the text for the current mode
+    fn render_content(
+        &self,
+        surface: &mut InlineSurface,
+        elapsed_ms: f32,
+        row: &mut usize,
+        col: &mut usize,
+        width: usize,
+        max_rows: usize,
+    ) {
+        if self.mode == Mode::Editing {
+            self.render_editable(surface, row, col, width, max_rows);
+        } else {
+            self.render_transcription(surface, elapsed_ms, row, col, width, max_rows);
+        }
+    }
+
+    /// First row of the text to show when it's taller than the `content_rows`
+    /// available, or 0 to show it from the top. The window follows the end of
+    /// the text (or the cursor while editing), and the first row is then
+    /// taken by a "more lines" indicator.
+    fn first_visible_row(&self, width: usize, content_rows: usize) -> usize {
+        if self.is_empty() || width == 0 || content_rows < 2 {
+            return 0;
+        }
+        let total_rows = wrapped_rows(&self.full_text(), 2, width, self.word_wrap);
+        if total_rows <= content_rows {
+            return 0;
+        }
+
+        let tail = total_rows - (content_rows - 1);
+        match self.mode {
+            Mode::Editing => tail.min(self.cursor_layout_position(width).1),
+            Mode::Listening => tail,
         }
     }
 
-    /// Get cursor position for terminal (if in editing mode)
-    pub fn cursor_screen_position(&self, width: usize) -> Option<(usize, usize)> {
-        if self.mode != Mode::Editing || width == 0 {
+    /// Get cursor position for terminal (if in editing mode), given the
+    /// surface size
+    pub fn cursor_screen_position(&self, width: usize, height: usize) -> Option<(usize, usize)> {
+        if self.mode != Mode::Editing || self.is_compact(width) {
             return None;
         }
 
-        let first_line_width = width.saturating_sub(2);
+        let (col, row) = self.cursor_layout_position(width);
+        let content_rows = if self.show_controls {
+            height.saturating_sub(1)
+        } else {
+            height
+        };
+        let row = match self.first_visible_row(width, content_rows) {
+            0 => row,
+            first => row - first + 1,
+        };
+        // Off screen when there's no room to scroll it into view
+        (row < content_rows).then_some((col, row))
+    }
+
+    /// Cursor position within the laid-out text (row 0 starts after the spinner)
+    fn cursor_layout_position(&self, width: usize) -> (usize, usize) {
+        // The cursor sits where the grapheme after it was laid out, or after
+        // the last one
+        let graphemes: Vec<&str> = self.frozen_text.graphemes(true).collect();
+        let layout = layout(&graphemes, 2, width, self.word_wrap);
+        let mut position = layout.end;
+        let mut char_idx = 0;
+        for (grapheme, &placed) in graphemes.iter().zip(&layout.positions) {
+            if char_idx >= self.cursor_pos {
+                position = placed;
+                break;
+            }
+            char_idx += grapheme.chars().count();
+        }
 
-        if self.cursor_pos < first_line_width {
-            // Cursor on first line (after spinner)
-            Some((self.cursor_pos + 2, 0))
+        // Cursor sitting past the last column shows at the start of the next row
+        let (col, row) = position;
+        if col >= width {
+            (0, row + 1)
         } else {
-            // Cursor on wrapped line
-            let pos_after_first = self.cursor_pos - first_line_width;
-            let row = 1 + pos_after_first / width;
-            let col = pos_after_first % width;
-            Some((col, row))
+            (col, row)
         }
     }
 
@@ -454,64 +925,83 @@ impl Ui {
         width: usize,
         max_rows: usize,
     ) {
-        let relative_time = elapsed_ms - self.animation_start_ms;
-        let white_attrs = self.attrs(self.white_color());
-
-        // Render frozen text (always white)
-        for ch in self.frozen_text.chars() {
-            if !self.render_char(surface, ch, white_attrs.clone(), row, col, width, max_rows) {
-                return;
-            }
+        let settled_attrs = self.attrs(self.settled_color());
+        let mut graphemes = Vec::new();
+        let mut attrs = Vec::new();
+
+        // Frozen text (always white)
+        for grapheme in self.frozen_text.graphemes(true) {
+            graphemes.push(grapheme);
+            attrs.push(Some(settled_attrs.clone()));
         }
 
-        // Render speech text:
-        // - chars < stable_len: white (stable, already animated)
-        // - chars >= stable_len: animate cyan→white
-        for (i, ch) in self.text.chars().enumerate() {
-            if i < self.stable_len {
-                // Stable character - render white
-                if !self.render_char(surface, ch, white_attrs.clone(), row, col, width, max_rows) {
-                    return;
-                }
+        // Speech text (stable_len and appear_ms are indexed by char):
+        // - graphemes starting before stable_len: white (stable, already animated)
+        // - graphemes starting at/after stable_len: animate cyan→white
+        let mut char_idx = 0;
+        for grapheme in self.text.graphemes(true) {
+            let start = char_idx;
+            char_idx += grapheme.chars().count();
+
+            let mut grapheme_attrs = if start < self.stable_len {
+                // Stable grapheme - render white
+                Some(settled_attrs.clone())
             } else {
-                // Unstable character - animate
-                let anim_index = i - self.stable_len;
-                let color = self.char_animation_color(anim_index, relative_time);
-                let Some(color) = color else { continue }; // Hidden chars (not visible yet)
-                if !self.render_char(surface, ch, self.attrs(color), row, col, width, max_rows) {
-                    return;
+                // Unstable grapheme - animate (None while not visible yet)
+                let appear_ms = self.appear_ms.get(start - self.stable_len);
+                let appear_ms = appear_ms.copied().unwrap_or_default();
+                self.char_animation_color(appear_ms, elapsed_ms)
+                    .map(|color| self.attrs(color))
+            };
+            // Rewritten text flashes behind
+            if let (Some(attrs), Some(correction)) = (&mut grapheme_attrs, self.correction) {
+                if correction.contains(start) {
+                    if let Some(color) = self.correction_color(correction, elapsed_ms) {
+                        attrs.set_background(color);
+                    }
                 }
             }
+            graphemes.push(grapheme);
+            attrs.push(grapheme_attrs);
         }
+
+        // Confirmed text after the insertion point
+        let tail = format!("{}{}", self.tail_separator(), self.frozen_tail);
+        for grapheme in tail.graphemes(true) {
+            graphemes.push(grapheme);
+            attrs.push(Some(settled_attrs.clone()));
+        }
+
+        self.render_graphemes(surface, &graphemes, attrs, row, col, width, max_rows);
     }
 
-    /// Render a single character, handling wrapping. Returns false if we've exceeded max_rows.
+    /// Draw graphemes where the layout puts them, starting at `row`/`col`,
+    /// and move `row`/`col` past them. Graphemes without attributes (not
+    /// faded in yet) keep their place but aren't drawn.
     #[allow(clippy::too_many_arguments)]
-    fn render_char(
+    fn render_graphemes(
         &self,
         surface: &mut InlineSurface,
-        ch: char,
-        attrs: CellAttributes,
+        graphemes: &[&str],
+        attrs: Vec<Option<CellAttributes>>,
         row: &mut usize,
         col: &mut usize,
         width: usize,
         max_rows: usize,
-    ) -> bool {
-        if *row >= max_rows {
-            return false;
-        }
-
-        if *col >= width {
-            *row += 1;
-            *col = 0;
-            if *row >= max_rows {
-                return false;
+    ) {
+        let layout = layout(graphemes, *col, width, self.word_wrap);
+        for ((&grapheme, attrs), &(x, y)) in graphemes.iter().zip(attrs).zip(&layout.positions) {
+            let y = *row + y;
+            if y >= max_rows {
+                break;
+            }
+            let Some(attrs) = attrs else { continue };
+            if !is_line_break(grapheme) {
+                surface.set_cell(x, y, Cell::new_grapheme(grapheme, attrs, None));
             }
         }
-
-        surface.set_cell(*col, *row, Cell::new(ch, attrs));
-        *col += 1;
-        true
+        *row += layout.end.1;
+        *col = layout.end.0;
     }
 
     fn render_editable(
@@ -523,13 +1013,10 @@ impl Ui {
         max_rows: usize,
     ) {
         // In edit mode, render frozen_text in white (that's where edits happen)
-        let attrs = self.attrs(self.white_color());
-
-        for ch in self.frozen_text.chars() {
-            if !self.render_char(surface, ch, attrs.clone(), row, col, width, max_rows) {
-                return;
-            }
-        }
+        let attrs = self.attrs(self.settled_color());
+        let graphemes: Vec<&str> = self.frozen_text.graphemes(true).collect();
+        let attrs = vec![Some(attrs); graphemes.len()];
+        self.render_graphemes(surface, &graphemes, attrs, row, col, width, max_rows);
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -543,32 +1030,60 @@ impl Ui {
         width: usize,
         max_rows: usize,
     ) {
-        for ch in text.chars() {
-            if *row >= max_rows || *col >= width {
+        for grapheme in text.graphemes(true) {
+            let w = grapheme_column_width(grapheme, None);
+            if *row >= max_rows || *col + w > width {
                 break;
             }
-            surface.set_cell(*col, *row, Cell::new(ch, attrs.clone()));
-            *col += 1;
+            surface.set_cell(
+                *col,
+                *row,
+                Cell::new_grapheme(grapheme, attrs.clone(), None),
+            );
+            *col += w;
         }
     }
 
-    fn render_controls(&self, surface: &mut InlineSurface, row: usize, width: usize) {
+    fn render_status(
+        &self,
+        surface: &mut InlineSurface,
+        message: &str,
+        color: ColorAttribute,
+        row: usize,
+        width: usize,
+    ) {
+        let attrs = self.attrs(color);
+        let mut col = 0;
+        for grapheme in message.graphemes(true) {
+            let w = grapheme_column_width(grapheme, None);
+            if col + w > width {
+                break;
+            }
+            surface.set_cell(col, row, Cell::new_grapheme(grapheme, attrs.clone(), None));
+            col += w;
+        }
+    }
+
+    /// Render the controls bar, returning the columns it took
+    fn render_controls(&self, surface: &mut InlineSurface, row: usize, width: usize) -> usize {
         let controls = match self.mode {
-            Mode::Listening => CONTROLS_LISTENING,
+            Mode::Listening => self.listening_controls.as_slice(),
             Mode::Editing => CONTROLS_EDITING,
         };
 
         // Calculate total width needed for full labels
         let full_width: usize = controls
             .iter()
-            .map(|c| c.key.len() + 1 + c.label.len() + 3) // "Key label • "
+            .map(|c| c.key.chars().count() + 1 + (c.label)(&self.messages).full.chars().count() + 3) // "Key label • "
             .sum::<usize>()
             .saturating_sub(3); // No separator after last
 
         // Calculate width for short labels
         let short_width: usize = controls
             .iter()
-            .map(|c| c.key.len() + 1 + c.short.len() + 3)
+            .map(|c| {
+                c.key.chars().count() + 1 + (c.label)(&self.messages).short.chars().count() + 3
+            })
             .sum::<usize>()
             .saturating_sub(3);
 
@@ -580,7 +1095,11 @@ impl Ui {
         for (i, ctrl) in controls.iter().enumerate() {
             // Separator
             if i > 0 && col < width {
-                let sep = if use_minimal { " " } else { " • " };
+                let sep = if use_minimal {
+                    " "
+                } else {
+                    self.glyphs.separator
+                };
                 for ch in sep.chars() {
                     if col >= width {
                         break;
@@ -610,7 +1129,8 @@ impl Ui {
                     col += 1;
                 }
 
-                let label = if use_short { ctrl.short } else { ctrl.label };
+                let label = (ctrl.label)(&self.messages);
+                let label = if use_short { &label.short } else { &label.full };
                 for ch in label.chars() {
                     if col >= width {
                         break;
@@ -620,58 +1140,135 @@ impl Ui {
                 }
             }
         }
+        col
+    }
+
+    /// Right-align the `--show-status` segment after `used` columns of
+    /// controls, leaving it out when it doesn't fit
+    fn render_engine_status(
+        &self,
+        surface: &mut InlineSurface,
+        row: usize,
+        width: usize,
+        used: usize,
+    ) {
+        if self.engine_status.is_empty() {
+            return;
+        }
+        let status = self.engine_status.join(self.glyphs.separator);
+        let status_width = status
+            .graphemes(true)
+            .map(|g| grapheme_column_width(g, None))
+            .sum::<usize>();
+        if used + 2 + status_width > width {
+            return;
+        }
+
+        let attrs = self.attrs(self.dim_color());
+        let mut col = width - status_width;
+        for grapheme in status.graphemes(true) {
+            surface.set_cell(col, row, Cell::new_grapheme(grapheme, attrs.clone(), None));
+            col += grapheme_column_width(grapheme, None);
+        }
     }
 
     // --- Spinner ---
 
     fn spinner_glyph(&self) -> (&'static str, ColorAttribute) {
+        if let Some(secs) = self.countdown {
+            let digit = COUNTDOWN_DIGITS[secs.min(9) as usize];
+            return (digit, ColorAttribute::PaletteIndex(3));
+        }
         match self.spinner_state {
-            SpinnerState::Loading => {
-                let idx = self.spinner_frame % LOADING_FRAMES.len();
-                (LOADING_FRAMES[idx], self.dim_color())
+            SpinnerState::Loading | SpinnerState::LoadingModel => {
+                let frames = self.glyphs.loading;
+                (frames[self.spinner_frame % frames.len()], self.dim_color())
+            }
+            SpinnerState::Reconnecting => {
+                let frames = self.glyphs.loading;
+                (
+                    frames[self.spinner_frame % frames.len()],
+                    ColorAttribute::PaletteIndex(3),
+                )
             }
             SpinnerState::Listening => {
-                let idx = (self.spinner_frame / 4) % RECORDING_FRAMES.len();
-                (RECORDING_FRAMES[idx], ColorAttribute::PaletteIndex(1))
+                let frames = self.glyphs.listening;
+                (
+                    frames[(self.spinner_frame / 4) % frames.len()],
+                    ColorAttribute::PaletteIndex(1),
+                )
             }
-            SpinnerState::Idle => ("○", self.dim_color()),
+            SpinnerState::Idle => (self.glyphs.idle, self.dim_color()),
         }
     }
 
     // --- Character animation ---
 
-    /// Calculate color for unsettled text character (animates cyan→white)
-    fn char_animation_color(&self, index: usize, relative_time: f32) -> Option<ColorAttribute> {
-        let appear_time = index as f32 * CHAR_FADE_DELAY_MS;
-
-        if relative_time < appear_time {
+    /// Calculate color for unsettled text character (animates cyan→white, or
+    /// teal→black on a light background)
+    fn char_animation_color(&self, appear_ms: f32, elapsed_ms: f32) -> Option<ColorAttribute> {
+        if elapsed_ms < appear_ms {
             return None; // Not visible yet
         }
 
-        let age = relative_time - appear_time;
-        let progress = (age / CHAR_FADE_DURATION_MS).min(1.0);
+        let age = elapsed_ms - appear_ms;
+        let progress = (age / self.animation.fade_ms).min(1.0);
         let eased = 1.0 - (1.0 - progress).powi(3); // ease-out cubic
 
-        // Cyan (120, 160, 180) → White (255, 255, 255)
-        let r = (120.0 + 135.0 * eased) / 255.0;
-        let g = (160.0 + 95.0 * eased) / 255.0;
-        let b = (180.0 + 75.0 * eased) / 255.0;
+        let (from, to) = match self.background {
+            Background::Dark => ([120.0, 160.0, 180.0], [255.0, 255.0, 255.0]),
+            Background::Light => ([0.0, 120.0, 150.0], [0.0, 0.0, 0.0]),
+        };
+        let channel = |i: usize| (from[i] + (to[i] - from[i]) * eased) / 255.0;
+
+        Some(self.rgb(channel(0), channel(1), channel(2)))
+    }
+
+    /// Background of rewritten text `elapsed_ms` into the flash, fading from
+    /// amber to the terminal background; `None` once it's over
+    fn correction_color(&self, correction: Correction, elapsed_ms: f32) -> Option<ColorAttribute> {
+        let age = elapsed_ms - correction.time_ms;
+        if self.color_mode == ColorMode::None || !(0.0..CORRECTION_FLASH_MS).contains(&age) {
+            return None;
+        }
 
-        Some(self.rgb(r, g, b))
+        let fade = 1.0 - age / CORRECTION_FLASH_MS;
+        let (tint, background) = match self.background {
+            Background::Dark => ([110.0, 80.0, 0.0], [0.0, 0.0, 0.0]),
+            Background::Light => ([255.0, 220.0, 140.0], [255.0, 255.0, 255.0]),
+        };
+        let channel = |i: usize| (background[i] + (tint[i] - background[i]) * fade) / 255.0;
+        Some(self.rgb(channel(0), channel(1), channel(2)))
     }
 
     // --- Color helpers ---
 
     fn attrs(&self, fg: ColorAttribute) -> CellAttributes {
+        if self.color_mode == ColorMode::None {
+            return CellAttributes::default();
+        }
         CellAttributes::default().set_foreground(fg).clone()
     }
 
     fn rgb(&self, r: f32, g: f32, b: f32) -> ColorAttribute {
-        ColorAttribute::TrueColorWithDefaultFallback(termwiz::color::SrgbaTuple(r, g, b, 1.0))
+        match self.color_mode {
+            ColorMode::TrueColor => ColorAttribute::TrueColorWithDefaultFallback(
+                termwiz::color::SrgbaTuple(r, g, b, 1.0),
+            ),
+            // Nearest entry in the xterm 6x6x6 color cube
+            ColorMode::Palette | ColorMode::None => {
+                let level = |c: f32| (c.clamp(0.0, 1.0) * 5.0).round() as u8;
+                ColorAttribute::PaletteIndex(16 + 36 * level(r) + 6 * level(g) + level(b))
+            }
+        }
     }
 
-    fn white_color(&self) -> ColorAttribute {
-        self.rgb(1.0, 1.0, 1.0)
+    /// Color of settled text: white, or black on a light background
+    fn settled_color(&self) -> ColorAttribute {
+        match self.background {
+            Background::Dark => self.rgb(1.0, 1.0, 1.0),
+            Background::Light => self.rgb(0.0, 0.0, 0.0),
+        }
     }
 
     fn dim_color(&self) -> ColorAttribute {
@@ -679,8 +1276,666 @@ impl Ui {
     }
 }
 
+/// Characters of the transcription the recognizer rewrote (rather than
+/// extended), and when
+#[derive(Clone, Copy, PartialEq)]
+struct Correction {
+    start: usize, // Char range in the new text
+    end: usize,
+    time_ms: f32,
+}
+
+impl Correction {
+    /// The part of `new_text` that differs from `old_text`, going by their
+    /// common prefix and suffix. Text that was only removed marks the
+    /// character before the gap.
+    fn new(old_text: &str, new_text: &str, time_ms: f32) -> Self {
+        let old: Vec<char> = old_text.chars().collect();
+        let new: Vec<char> = new_text.chars().collect();
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let end = new.len() - suffix;
+        let start = if end > prefix {
+            prefix
+        } else {
+            prefix.saturating_sub(1)
+        };
+        Self {
+            start,
+            end: end.max(start + 1).min(new.len()),
+            time_ms,
+        }
+    }
+
+    fn contains(&self, char_idx: usize) -> bool {
+        (self.start..self.end).contains(&char_idx)
+    }
+}
+
+/// Place a grapheme of display width `w` at the layout position, wrapping to
+/// the next row when it doesn't fit (wide glyphs never split across rows).
+/// Returns the (col, row) where it was placed and advances the position.
+fn advance(col: &mut usize, row: &mut usize, w: usize, width: usize) -> (usize, usize) {
+    if *col + w > width && *col > 0 {
+        *row += 1;
+        *col = 0;
+    }
+    let placed = (*col, *row);
+    *col += w;
+    placed
+}
+
+fn is_line_break(grapheme: &str) -> bool {
+    grapheme == "\n" || grapheme == "\r\n"
+}
+
+/// Spaces and line breaks, which end a word
+fn is_space(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+/// Where laid-out graphemes go
+struct Layout {
+    /// (col, row) of each grapheme; a line break's is where it starts
+    positions: Vec<(usize, usize)>,
+    /// Position after the last grapheme
+    end: (usize, usize),
+}
+
+/// Lay out graphemes from `start_col`, wrapping at `width` by display width.
+/// A line break moves to the start of the next row. With `word_wrap`, a word
+/// that doesn't fit in the rest of the row moves to the next one (a word
+/// wider than a whole row is still split), and a space just past the end of
+/// a row is dropped instead of starting the next one.
+fn layout(graphemes: &[&str], start_col: usize, width: usize, word_wrap: bool) -> Layout {
+    let mut positions = Vec::with_capacity(graphemes.len());
+    let mut col = start_col;
+    let mut row = 0;
+    for (i, &grapheme) in graphemes.iter().enumerate() {
+        if is_line_break(grapheme) {
+            positions.push((col, row));
+            row += 1;
+            col = 0;
+            continue;
+        }
+
+        if word_wrap && col > 0 {
+            if grapheme == " " && col >= width {
+                positions.push((col, row));
+                continue;
+            }
+            let starts_word = !is_space(grapheme) && (i == 0 || is_space(graphemes[i - 1]));
+            if starts_word {
+                let word_width: usize = graphemes[i..]
+                    .iter()
+                    .take_while(|grapheme| !is_space(grapheme))
+                    .map(|grapheme| grapheme_column_width(grapheme, None))
+                    .sum();
+                if col + word_width > width && word_width <= width {
+                    row += 1;
+                    col = 0;
+                }
+            }
+        }
+
+        let w = grapheme_column_width(grapheme, None);
+        positions.push(advance(&mut col, &mut row, w, width));
+    }
+    Layout {
+        positions,
+        end: (col, row),
+    }
+}
+
+/// Number of rows `text` occupies when laid out from `start_col`
+fn wrapped_rows(text: &str, start_col: usize, width: usize, word_wrap: bool) -> usize {
+    let graphemes: Vec<&str> = text.graphemes(true).collect();
+    layout(&graphemes, start_col, width, word_wrap).end.1 + 1
+}
+
+/// Work out the stable prefix length and when each remaining character
+/// starts fading in, after the transcription changes from `old_text` to
+/// `new_text`. Returns `(stable_len, appear_ms)`.
+///
+/// - The stable prefix never shrinks unless the text itself gets shorter
+///   (recognizer retracting words)
+/// - Unstable characters that survive the update keep their fade times, found
+///   by aligning the old and new unstable text, so a recognizer rewriting one
+///   word doesn't restart the fade of the words around it
+/// - If less than `MIN_KEPT_FRACTION` survives, the hypothesis was replaced
+///   and everything restarts
+/// - New characters start fading at `elapsed_ms`, but no sooner than
+///   `char_delay_ms` after the character before them (and no later than
+///   `MAX_APPEAR_LAG_MS` from now)
+/// - Leading characters whose fade has finished become stable
+fn stable_update(
+    old_text: &str,
+    old_stable_len: usize,
+    old_appear_ms: &[f32],
+    new_text: &str,
+    elapsed_ms: f32,
+    animation: &Animation,
+) -> (usize, Vec<f32>) {
+    let old_chars: Vec<char> = old_text.chars().collect();
+    let new_chars: Vec<char> = new_text.chars().collect();
+
+    let stable_len = old_stable_len.min(new_chars.len());
+    let old_unstable = &old_chars[old_stable_len.min(old_chars.len())..];
+    let new_unstable = &new_chars[stable_len..];
+
+    let matches = align(old_unstable, new_unstable);
+    let kept = matches.iter().flatten().count();
+    let keep = kept as f32 >= new_unstable.len() as f32 * MIN_KEPT_FRACTION;
+
+    let mut appear_ms: Vec<f32> = Vec::with_capacity(new_unstable.len());
+    for matched in matches {
+        let previous = appear_ms.last().copied();
+        let time = match matched.and_then(|i| old_appear_ms.get(i)) {
+            Some(&time) if keep => time,
+            _ => previous.map_or(elapsed_ms, |previous| {
+                (previous + animation.char_delay_ms)
+                    .clamp(elapsed_ms, elapsed_ms + MAX_APPEAR_LAG_MS)
+            }),
+        };
+        appear_ms.push(time);
+    }
+
+    let settled = appear_ms
+        .iter()
+        .take_while(|&&appear| appear + animation.fade_ms <= elapsed_ms)
+        .count();
+    appear_ms.drain(..settled);
+    (stable_len + settled, appear_ms)
+}
+
+/// Pair up the characters of `new` with equal ones in `old` along a longest
+/// common subsequence: for each character of `new`, its index in `old`, or
+/// `None` if it's new
+fn align(old: &[char], new: &[char]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; new.len()];
+    if old.is_empty() || old.len() * new.len() > MAX_ALIGN_CELLS {
+        return matches;
+    }
+
+    // lcs[i * width + j]: length of the LCS of old[i..] and new[j..]
+    let width = new.len() + 1;
+    let mut lcs = vec![0usize; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old[i] == new[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            matches[j] = Some(i);
+            i += 1;
+            j += 1;
+        } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    matches
+}
+
 impl Default for Ui {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use termwiz::surface::change::Change;
+
+    /// Each row of `ui` drawn on a `width` × `height` surface, as text
+    fn render(ui: &mut Ui, width: usize, height: usize) -> Vec<String> {
+        let mut surface = InlineSurface::new(width, height);
+        ui.render(&mut surface, 1e9);
+        (0..height)
+            .map(|row| {
+                let mut text = String::new();
+                for change in surface.get_line_content(row) {
+                    if let Change::Text(t) = change {
+                        text.push_str(&t);
+                    }
+                }
+                text.trim_end().to_string()
+            })
+            .collect()
+    }
+
+    /// A UI showing `text` as confirmed (fully faded in) text
+    fn ui_with(text: &str) -> Ui {
+        let mut ui = Ui::new();
+        ui.set_frozen_text(text.to_string());
+        ui
+    }
+
+    #[test]
+    fn wide_text_line_count_matches_render() {
+        for text in ["日本語のテキストです", "👍👍👍👍👍", "漢字👍かな🎉ひらがな"]
+        {
+            let mut ui = ui_with(text);
+            let needed = ui.lines_needed(10);
+            let rows = render(&mut ui, 10, 8);
+            let drawn = rows.iter().rposition(|row| !row.is_empty()).unwrap() + 1;
+            assert_eq!(needed, drawn, "{text}");
+            assert_eq!(needed, wrapped_rows(text, 2, 10, true), "{text}");
+        }
+    }
+
+    #[test]
+    fn wide_text_wraps_by_display_width() {
+        // 10 double-width characters after the 2-column spinner: 4, 5, 1
+        assert_eq!(wrapped_rows("日本語のテキストです", 2, 10, false), 3);
+        // 5 emoji: 4 fit after the spinner, 1 wraps
+        assert_eq!(wrapped_rows("👍👍👍👍👍", 2, 10, false), 2);
+        // A wide glyph that doesn't fit in the last column wraps whole
+        assert_eq!(wrapped_rows("abcdefg漢", 2, 10, false), 2);
+    }
+
+    #[test]
+    fn mixed_english_japanese_renders_every_grapheme() {
+        let text = "Hello 世界, this is 日本語 mixed with English";
+        let mut ui = Ui::new();
+        ui.set_text(text, 0.0);
+        let rows = render(&mut ui, 12, 8);
+
+        // Nothing overlaps or goes missing: the rows read back as the text,
+        // split only at the spaces word wrap broke the lines at
+        let words: Vec<String> = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(i, row)| {
+                let row = if i == 0 {
+                    &row[row.find(' ').unwrap() + 1..]
+                } else {
+                    row
+                };
+                row.split(' ').map(str::to_string).collect::<Vec<_>>()
+            })
+            .filter(|word| !word.is_empty())
+            .collect();
+        assert_eq!(words.join(" "), text);
+        for row in &rows {
+            let width: usize = row
+                .graphemes(true)
+                .map(|g| grapheme_column_width(g, None))
+                .sum();
+            assert!(width <= 12, "{row:?}");
+        }
+        assert_eq!(
+            rows.iter().filter(|row| !row.is_empty()).count(),
+            ui.lines_needed(12)
+        );
+    }
+
+    #[test]
+    fn tiny_widths_show_only_the_spinner() {
+        for width in 0..=2 {
+            let mut ui = ui_with("Hello there, this is a long line of text");
+            ui.show_controls = true;
+            assert_eq!(ui.lines_needed(width), 1, "width {width}");
+            let rows = render(&mut ui, width, 3);
+            assert!(rows[1..].iter().all(|row| row.is_empty()), "{rows:?}");
+            if width == 0 {
+                assert!(rows[0].is_empty());
+            } else {
+                assert_eq!(rows[0].chars().count(), 1, "{rows:?}");
+            }
+
+            // Nowhere to show the cursor
+            ui.start_editing();
+            assert_eq!(ui.lines_needed(width), 1, "width {width}");
+            assert_eq!(ui.cursor_screen_position(width, 1), None);
+            render(&mut ui, width, 3);
+        }
+    }
+
+    #[test]
+    fn tiny_widths_lay_out_without_looping() {
+        // Compact mode never gets here, but the layout must still end
+        for width in 0..=2 {
+            assert_eq!(wrapped_rows("", 2, width, true), 1);
+            assert!(wrapped_rows("ab cd", 2, width, true) <= 6);
+            assert!(wrapped_rows("日本", 2, width, false) <= 3);
+        }
+    }
+
+    #[test]
+    fn cancel_editing_restores_text_from_before_editing() {
+        let mut ui = ui_with("Hello ");
+        ui.set_text("world", 0.0);
+        let original = ui.full_text();
+
+        ui.start_editing();
+        ui.cursor_home();
+        ui.delete_forward();
+        ui.insert_char('J');
+        ui.cursor_end();
+        ui.delete_back();
+        assert_eq!(ui.full_text(), "Jello worl");
+
+        ui.cancel_editing();
+        assert!(ui.mode == Mode::Listening);
+        // Back to the original, with a space for the next speech
+        assert_eq!(ui.full_text(), format!("{original} "));
+    }
+
+    /// `stable_update` with the default animation timing (20ms between
+    /// characters, 1500ms fade)
+    fn update(old: &str, stable: usize, appear: &[f32], new: &str, at: f32) -> (usize, Vec<f32>) {
+        stable_update(old, stable, appear, new, at, &Animation::default())
+    }
+
+    #[test]
+    fn saving_an_edit_keeps_words_apart() {
+        let mut ui = Ui::new();
+        ui.set_text("hello", 0.0);
+        ui.start_editing();
+        ui.finish_editing_with_freeze();
+        ui.set_text("goodbye", 0.0);
+        assert_eq!(ui.full_text(), "hello goodbye");
+    }
+
+    #[test]
+    fn cancelling_an_edit_keeps_words_apart() {
+        let mut ui = Ui::new();
+        ui.set_text("hello", 0.0);
+        ui.start_editing();
+        ui.cursor_end();
+        ui.delete_back();
+        ui.cancel_editing();
+        ui.set_text("goodbye", 0.0);
+        assert_eq!(ui.full_text(), "hello goodbye");
+    }
+
+    #[test]
+    fn speech_inserted_mid_text_keeps_words_apart() {
+        let mut ui = Ui::new();
+        ui.set_text("hello world", 0.0);
+        ui.start_editing();
+        ui.cursor_home();
+        for _ in 0.."hello".len() {
+            ui.cursor_right();
+        }
+        ui.finish_editing_with_freeze();
+        ui.set_text("there", 0.0);
+        assert_eq!(ui.full_text(), "hello there world");
+    }
+
+    /// "a", a flag (two code points), "e" with two combining accents, "b"
+    const CLUSTERS: &str = "a\u{1F1E9}\u{1F1EA}e\u{301}\u{302}b";
+
+    #[test]
+    fn cursor_moves_over_whole_graphemes() {
+        let mut ui = ui_with(CLUSTERS);
+        ui.start_editing();
+        assert_eq!(ui.cursor_pos, 7);
+
+        let mut stops = vec![];
+        for _ in 0..5 {
+            ui.cursor_left();
+            stops.push(ui.cursor_pos);
+        }
+        assert_eq!(stops, [6, 3, 1, 0, 0]);
+
+        stops.clear();
+        for _ in 0..5 {
+            ui.cursor_right();
+            stops.push(ui.cursor_pos);
+        }
+        assert_eq!(stops, [1, 3, 6, 7, 7]);
+    }
+
+    #[test]
+    fn cursor_column_counts_display_width() {
+        let mut ui = ui_with(CLUSTERS);
+        ui.start_editing();
+        // After the spinner: "a" at 2, the flag at 3-4, the accented "e" at
+        // 5 and "b" at 6
+        let mut columns = vec![];
+        ui.cursor_home();
+        for _ in 0..4 {
+            columns.push(ui.cursor_layout_position(20).0);
+            ui.cursor_right();
+        }
+        columns.push(ui.cursor_layout_position(20).0);
+        assert_eq!(columns, [2, 3, 5, 6, 7]);
+    }
+
+    #[test]
+    fn delete_back_removes_whole_graphemes() {
+        let mut ui = ui_with(CLUSTERS);
+        ui.start_editing();
+        ui.delete_back();
+        assert_eq!(ui.full_text(), "a\u{1F1E9}\u{1F1EA}e\u{301}\u{302}");
+        ui.delete_back();
+        assert_eq!(ui.full_text(), "a\u{1F1E9}\u{1F1EA}");
+        assert_eq!(ui.cursor_pos, 3);
+        ui.delete_back();
+        assert_eq!(ui.full_text(), "a");
+        assert_eq!(ui.cursor_pos, 1);
+    }
+
+    #[test]
+    fn delete_forward_removes_whole_graphemes() {
+        let mut ui = ui_with(CLUSTERS);
+        ui.start_editing();
+        ui.cursor_home();
+        ui.cursor_right();
+        ui.delete_forward();
+        assert_eq!(ui.full_text(), "ae\u{301}\u{302}b");
+        ui.delete_forward();
+        assert_eq!(ui.full_text(), "ab");
+        assert_eq!(ui.cursor_pos, 1);
+
+        // Typing lands between the clusters, not inside one
+        let mut ui = ui_with(CLUSTERS);
+        ui.start_editing();
+        ui.cursor_left();
+        ui.cursor_left();
+        ui.insert_char('x');
+        assert_eq!(ui.full_text(), "a\u{1F1E9}\u{1F1EA}xe\u{301}\u{302}b");
+    }
+
+    #[test]
+    fn stable_update_first_text() {
+        let (stable, appear) = update("", 0, &[], "Hi you", 100.0);
+        assert_eq!(stable, 0);
+        assert_eq!(appear, [100.0, 120.0, 140.0, 160.0, 180.0, 200.0]);
+    }
+
+    #[test]
+    fn stable_update_append_keeps_fade_times() {
+        let (stable, appear) = update("", 0, &[], "Hello", 0.0);
+        let (stable, appear) = update("Hello", stable, &appear, "Hello you", 30.0);
+        assert_eq!(stable, 0);
+        // The new characters queue up behind the old ones
+        assert_eq!(
+            appear,
+            [0.0, 20.0, 40.0, 60.0, 80.0, 100.0, 120.0, 140.0, 160.0]
+        );
+
+        // Long after, the appended text starts right away
+        let (stable, appear) = update("Hello", 0, &appear[..5], "Hello you", 1000.0);
+        assert_eq!(stable, 0);
+        assert_eq!(&appear[4..7], [80.0, 1000.0, 1020.0]);
+    }
+
+    #[test]
+    fn stable_update_settled_text_becomes_stable() {
+        let old_appear = [0.0, 20.0, 40.0, 60.0, 80.0];
+        let (stable, appear) = update("Hello", 0, &old_appear, "Hello you", 1530.0);
+        // "He" has finished fading (20 + 1500 <= 1530), the rest hasn't
+        assert_eq!(stable, 2);
+        assert_eq!(&appear[..3], [40.0, 60.0, 80.0]);
+        assert_eq!(appear.len(), 7);
+    }
+
+    #[test]
+    fn stable_update_correction() {
+        let old = "Hi word";
+        let old_appear: Vec<f32> = (0..7).map(|i| i as f32 * 20.0).collect();
+        let (stable, appear) = update(old, 0, &old_appear, "Hi world", 200.0);
+        assert_eq!(stable, 0);
+        // Everything but the inserted "l" keeps its time
+        assert_eq!(&appear[..6], &old_appear[..6]);
+        assert_eq!(appear[7], old_appear[6]);
+        assert_eq!(appear[6], 200.0);
+    }
+
+    #[test]
+    fn align_pairs_up_unchanged_characters() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(
+            align(&chars("a cat"), &chars("a dog")),
+            [Some(0), Some(1), None, None, None]
+        );
+        // An insertion shifts the pairing of what follows
+        assert_eq!(
+            align(&chars("abc"), &chars("aXbc")),
+            [Some(0), None, Some(1), Some(2)]
+        );
+        assert_eq!(align(&[], &chars("ab")), [None, None]);
+    }
+
+    #[test]
+    fn stable_update_one_word_rewrite_keeps_the_rest() {
+        let old = "I like the cat here";
+        let old_appear: Vec<f32> = (0..19).map(|i| i as f32 * 20.0).collect();
+        let (stable, appear) = update(old, 0, &old_appear, "I like the dog here", 500.0);
+        assert_eq!(stable, 0);
+        // The words around the rewritten one keep their fade times
+        assert_eq!(&appear[..11], &old_appear[..11]);
+        assert_eq!(&appear[14..], &old_appear[14..]);
+        // The new word starts fading now
+        assert_eq!(&appear[11..14], [500.0, 520.0, 540.0]);
+    }
+
+    #[test]
+    fn stable_update_restarts_fade_when_most_text_is_replaced() {
+        let old_appear = [0.0, 20.0, 40.0, 60.0];
+        // Half of the new text survives: kept
+        let (_, appear) = update("abcd", 0, &old_appear, "abcXYZ", 500.0);
+        assert_eq!(appear, [0.0, 20.0, 40.0, 500.0, 520.0, 540.0]);
+        // Less than half: a new hypothesis, everything fades in again
+        let (_, appear) = update("abcd", 0, &old_appear, "abWXYZ", 500.0);
+        assert_eq!(appear, [500.0, 520.0, 540.0, 560.0, 580.0, 600.0]);
+    }
+
+    #[test]
+    fn long_bursts_dont_fall_behind() {
+        // 50 characters every 50ms, far more than one per char_delay_ms
+        let mut ui = Ui::new();
+        let mut text = String::new();
+        let mut now = 0.0;
+        for _ in 0..100 {
+            now += 50.0;
+            text.push_str(&"word ".repeat(10));
+            ui.set_text(&text, now);
+        }
+        assert_eq!(text.len(), 5000);
+
+        // The newest character is at most MAX_APPEAR_LAG_MS behind, and
+        // shows in the start color when it appears
+        let newest = *ui.appear_ms.last().unwrap();
+        assert!(newest <= now + MAX_APPEAR_LAG_MS, "{newest}");
+        let start_color = ui.char_animation_color(0.0, 0.0);
+        assert_eq!(ui.char_animation_color(newest, newest), start_color);
+        assert_eq!(ui.char_animation_color(newest, newest - 1.0), None);
+        // Rather than fading in tens of seconds from now
+        assert_ne!(
+            ui.char_animation_color(newest, now + MAX_APPEAR_LAG_MS + 1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn stable_update_retraction() {
+        // The recognizer takes back a word that had already settled
+        let (stable, appear) = update("Hi there", 8, &[], "Hi", 5000.0);
+        assert_eq!(stable, 2);
+        assert!(appear.is_empty());
+
+        // And one still fading in
+        let (stable, appear) = update("Hi there", 3, &[0.0; 5], "Hi th", 100.0);
+        assert_eq!(stable, 3);
+        assert_eq!(appear, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn stable_update_prefix_never_shrinks() {
+        // Even when the text under it changes, as long as it isn't shorter
+        for new in ["Hi there", "Ho there", "Hi you, hello", "Bye now!"] {
+            let (stable, _) = update("Hi you", 3, &[0.0; 3], new, 100.0);
+            assert_eq!(stable, 3, "{new}");
+        }
+        let (stable, _) = update("Hi you", 3, &[0.0; 3], "Hi", 100.0);
+        assert_eq!(stable, 2);
+    }
+
+    #[test]
+    fn line_breaks_start_new_rows() {
+        assert_eq!(wrapped_rows("ab\ncd", 2, 10, false), 2);
+        assert_eq!(wrapped_rows("ab\n\ncd", 2, 10, false), 3);
+        assert_eq!(wrapped_rows("ab\r\ncd", 2, 10, false), 2);
+        // A trailing line break still takes a row for what comes next
+        assert_eq!(wrapped_rows("ab\n", 2, 10, false), 2);
+        assert_eq!(wrapped_rows("\n", 2, 10, false), 2);
+    }
+
+    #[test]
+    fn spinner_offset_is_only_on_the_first_row() {
+        // 8 columns fit after the spinner, the full 10 on later rows
+        assert_eq!(wrapped_rows("abcdefgh", 2, 10, false), 1);
+        assert_eq!(wrapped_rows("abcdefghi", 2, 10, false), 2);
+        assert_eq!(wrapped_rows("abcdefgh0123456789", 2, 10, false), 2);
+        assert_eq!(wrapped_rows("abcdefgh0123456789x", 2, 10, false), 3);
+        // Including rows started by a line break
+        assert_eq!(wrapped_rows("a\n0123456789", 2, 10, false), 2);
+        assert_eq!(wrapped_rows("a\n0123456789x", 2, 10, false), 3);
+    }
+
+    #[test]
+    fn lines_needed_counts_line_breaks() {
+        let ui = ui_with("one\ntwo\n\nfour");
+        assert_eq!(ui.lines_needed(20), 4);
+    }
+
+    #[test]
+    fn cursor_after_a_full_last_row_takes_another_row() {
+        // Exactly fills the first row after the spinner
+        let mut ui = ui_with("abcdefgh");
+        assert_eq!(ui.lines_needed(10), 1);
+
+        ui.start_editing();
+        ui.cursor_end();
+        assert_eq!(ui.cursor_layout_position(10), (0, 1));
+        assert_eq!(ui.lines_needed(10), 2);
+
+        // But not when the cursor is elsewhere
+        ui.cursor_home();
+        assert_eq!(ui.lines_needed(10), 1);
+
+        // The same for a full later row
+        let mut ui = ui_with("abcdefgh0123456789");
+        ui.start_editing();
+        ui.cursor_end();
+        assert_eq!(ui.lines_needed(10), 3);
+    }
+}
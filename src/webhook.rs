@@ -0,0 +1,88 @@
+//! Posting the final transcription to an HTTP endpoint (`--webhook URL`)
+//!
+//! Built with `--features http`, the request is sent by claudio itself.
+//! Otherwise it's sent with curl, which ships with macOS, Windows 10 and
+//! later, and most Linux systems, so default builds don't need an HTTP and
+//! TLS stack of their own. The body is JSON:
+//!
+//! ```json
+//! {"text": "turn on the lights", "duration_ms": 2140, "backend": "vosk"}
+//! ```
+
+#[cfg(not(feature = "http"))]
+use std::io::{ErrorKind, Write};
+#[cfg(not(feature = "http"))]
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+
+/// Longest the request may take, so a hung server can't hold up the output
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// POST `text`, how long recording took and the engine used to `url`
+pub fn post(url: &str, text: &str, duration: Duration, backend: &str) -> Result<()> {
+    let body = serde_json::json!({
+        "text": text,
+        "duration_ms": duration.as_millis() as u64,
+        "backend": backend,
+    });
+    send(url, &body.to_string())?;
+    log::debug!("Posted the transcription to {}", url);
+    Ok(())
+}
+
+#[cfg(feature = "http")]
+fn send(url: &str, body: &str) -> Result<()> {
+    let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+    match agent
+        .post(url)
+        .set("Content-Type", "application/json")
+        .send_string(body)
+    {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, response)) => Err(anyhow!(
+            "Webhook {} failed: HTTP {} {}",
+            url,
+            code,
+            response.status_text()
+        )),
+        Err(e) => Err(anyhow!("Webhook {} failed: {}", url, e)),
+    }
+}
+
+#[cfg(not(feature = "http"))]
+fn send(url: &str, body: &str) -> Result<()> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time"])
+        .arg(TIMEOUT.as_secs().to_string())
+        .args(["--header", "Content-Type: application/json"])
+        .args(["--data-binary", "@-", "--url", url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => anyhow!("--webhook needs curl, which wasn't found"),
+            _ => anyhow!("Failed to run curl: {}", e),
+        })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        if let Err(e) = stdin.write_all(body.as_bytes()) {
+            // Don't leave it running (or a zombie) behind
+            drop(stdin);
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow!("Failed to write to curl: {}", e));
+        }
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Webhook {} failed: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}